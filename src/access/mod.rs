@@ -3,7 +3,22 @@ pub mod sysfs;
 
 use crate::error::Result;
 
+/// Where a backend gets the Vital Product Data of its function from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VpdSource {
+    /// The backend has no VPD reader of its own, VPD is read with the capability handshake.
+    Handshake,
+    /// The backend reads VPD itself but has none for the function.
+    Unavailable,
+    /// The VPD as read by the backend.
+    Data(Vec<u8>),
+}
+
 pub trait Access {
     fn read(&self, offset: u64, length: usize) -> Result<Vec<u8>>;
     fn write(&self, offset: u64, value: &[u8]) -> Result<usize>;
+
+    fn vpd(&self) -> Result<VpdSource> {
+        Ok(VpdSource::Handshake)
+    }
 }
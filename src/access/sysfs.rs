@@ -5,8 +5,13 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use std::str::FromStr;
 
-use crate::access::Access;
+use crate::access::{Access, VpdSource};
 use crate::bdf::BusDeviceFunction;
+use crate::caps::acs;
+use crate::caps::pci_express::PciExpressCapability;
+use crate::caps::ptm;
+use crate::caps::registry::CapabilityRegistry;
+use crate::caps::PathViolation;
 use crate::error::Result;
 use crate::function::Function;
 use crate::kernel::Kernel;
@@ -18,6 +23,10 @@ impl Sysfs {
     const PCI_FUNCTIONS_PATH: &str = "/sys/bus/pci/devices";
 
     pub fn discover() -> Result<Vec<Function>> {
+        Self::discover_with_registry(Rc::new(CapabilityRegistry::default()))
+    }
+
+    pub fn discover_with_registry(registry: Rc<CapabilityRegistry>) -> Result<Vec<Function>> {
         let mut bdfs = vec![];
 
         for entry in fs::read_dir(Self::PCI_FUNCTIONS_PATH)? {
@@ -36,16 +45,113 @@ impl Sysfs {
 
         let mut functions = vec![];
         for bdf in bdfs {
-            functions.push(Function::new(
+            let access: Rc<Box<dyn Access>> = Rc::new(Box::new(SysfsAccess::new(bdf)));
+
+            // Below a port with ARI Forwarding enabled the kernel still uses a conventional
+            // device and function number, show the ARI function number instead
+            let bdf = match Self::ari_forwarding(&bdf) {
+                true => bdf.to_ari(),
+                false => bdf,
+            };
+
+            functions.push(Function::with_registry(
                 bdf,
-                Rc::new(Box::new(SysfsAccess::new(bdf))),
+                access,
                 Kernel,
+                Rc::clone(&registry),
             )?);
         }
 
         Ok(functions)
     }
 
+    /// Returns the bridges above `bdf`, starting with its parent and ending with the root port.
+    pub fn upstream_bridges(bdf: &BusDeviceFunction) -> Result<Vec<BusDeviceFunction>> {
+        let path = fs::canonicalize(Self::get_function_sub_path(bdf, ""))?;
+
+        let mut bridges: Vec<_> = path
+            .components()
+            .filter_map(|component| component.as_os_str().to_str())
+            .filter_map(|component| BusDeviceFunction::from_str(component).ok())
+            .filter(|bridge| bridge.routing_id().is_some())
+            .collect();
+
+        bridges.pop();
+        bridges.reverse();
+
+        Ok(bridges)
+    }
+
+    /// Returns the other PCI functions on the same bus segment as `bdf`.
+    pub fn sibling_functions(bdf: &BusDeviceFunction) -> Result<Vec<BusDeviceFunction>> {
+        let path = fs::canonicalize(Self::get_function_sub_path(bdf, ""))?;
+
+        let mut siblings = vec![];
+        if let Some(parent) = path.parent() {
+            for entry in fs::read_dir(parent)? {
+                let entry = entry?;
+                let sibling = match entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| BusDeviceFunction::from_str(name).ok())
+                {
+                    Some(sibling) if sibling.routing_id().is_some() => sibling,
+                    _ => continue,
+                };
+
+                // Only PCI devices have a config file, other children of the bridge do not
+                if sibling != *bdf && entry.path().join("config").is_file() {
+                    siblings.push(sibling);
+                }
+            }
+        }
+
+        Ok(siblings)
+    }
+
+    /// Whether the downstream port above `bdf` has ARI Forwarding enabled in DevCtl2.
+    pub fn ari_forwarding(bdf: &BusDeviceFunction) -> bool {
+        let parent = match Self::upstream_bridges(bdf) {
+            Ok(bridges) if !bridges.is_empty() => bridges[0],
+            _ => return false,
+        };
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(SysfsAccess::new(parent)));
+        match PciExpressCapability::find(&access) {
+            Ok(Some(cap)) => cap
+                .device_control2()
+                .is_ok_and(|control| control & (1 << 5) != 0),
+            _ => false,
+        }
+    }
+
+    /// Returns `bdf` followed by its upstream bridges up to the root port.
+    pub fn upstream_path(bdf: &BusDeviceFunction) -> Result<Vec<Function>> {
+        let mut path = vec![];
+
+        for function in [*bdf].into_iter().chain(Self::upstream_bridges(bdf)?) {
+            path.push(Function::new(
+                function,
+                Rc::new(Box::new(SysfsAccess::new(function))),
+                Kernel,
+            )?);
+        }
+
+        Ok(path)
+    }
+
+    /// Checks whether the ACS controls between `bdf` and its root port isolate it for device
+    /// assignment. See `acs::path_violations`.
+    pub fn acs_path_violations(bdf: &BusDeviceFunction) -> Result<Vec<PathViolation>> {
+        acs::path_violations(&Self::upstream_path(bdf)?)
+    }
+
+    /// Checks whether PTM is enabled between `bdf` and its root port. See
+    /// `ptm::path_violations`.
+    pub fn ptm_path_violations(bdf: &BusDeviceFunction) -> Result<Vec<PathViolation>> {
+        ptm::path_violations(&Self::upstream_path(bdf)?)
+    }
+
     pub fn get_function_sub_path(bdf: &BusDeviceFunction, sub: &str) -> PathBuf {
         let mut path: PathBuf = Self::PCI_FUNCTIONS_PATH.into();
 
@@ -175,8 +281,7 @@ impl Access for SysfsAccess {
         let path = Sysfs::get_function_sub_path(&self.bdf, "config");
         let file = fs::File::open(path)?;
 
-        let mut buffer = Vec::new();
-        buffer.resize(length, 0);
+        let mut buffer = vec![0; length];
 
         file.read_exact_at(&mut buffer[..], offset)?;
 
@@ -189,4 +294,16 @@ impl Access for SysfsAccess {
 
         Ok(file.write_at(buffer, offset)?)
     }
+
+    // The kernel hides the vpd attribute of devices with broken VPD, which must not be read
+    // through config space either
+    fn vpd(&self) -> Result<VpdSource> {
+        match fs::read(Sysfs::get_function_sub_path(&self.bdf, "vpd")) {
+            Ok(vpd) => Ok(VpdSource::Data(vpd)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(VpdSource::Unavailable)
+            }
+            Err(error) => Err(error.into()),
+        }
+    }
 }
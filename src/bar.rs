@@ -1,17 +1,17 @@
 use std::fmt::Display;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MemBAR<T> {
     pub address: T,
     pub prefechable: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IoBAR {
     pub address: u32,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BAR {
     MemBAR32(MemBAR<u32>),
     MemBAR64(MemBAR<u64>),
@@ -55,7 +55,7 @@ impl BAR {
         let mut offset = 0;
         let mut bars = vec![];
 
-        while offset < b.len() {
+        while offset + 4 <= b.len() {
             let word = u32::from_le_bytes(b[offset..offset + 4].try_into().unwrap());
             if Self::is_io_bar(word) {
                 bars.push(Self::io_bar(word));
@@ -63,6 +63,10 @@ impl BAR {
             } else if Self::is_32bit_mem_bar(word) {
                 bars.push(Self::mem_32bit_bar(word));
                 offset += 4;
+            } else if offset + 8 > b.len() {
+                log::warn!("64-bit BAR in the last register, decoding it as 32-bit");
+                bars.push(Self::mem_32bit_bar(word));
+                offset += 4;
             } else {
                 offset += 4;
                 let next_word = u32::from_le_bytes(b[offset..offset + 4].try_into().unwrap());
@@ -74,6 +78,35 @@ impl BAR {
         bars
     }
 
+    /// Returns the BAR starting at register `index` of `bars`.
+    pub fn at(bars: Vec<BAR>, index: u8) -> Option<BAR> {
+        let mut register = 0;
+
+        for bar in bars {
+            if register == index {
+                return Some(bar);
+            }
+            register += bar.registers();
+        }
+
+        None
+    }
+
+    pub fn registers(&self) -> u8 {
+        match self {
+            BAR::MemBAR64(_) => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn address(&self) -> u64 {
+        match self {
+            BAR::IoBAR(b) => b.address.into(),
+            BAR::MemBAR32(b) => b.address.into(),
+            BAR::MemBAR64(b) => b.address,
+        }
+    }
+
     pub fn is_allocated(&self) -> bool {
         match self {
             BAR::IoBAR(b) => b.address != 0,
@@ -14,10 +14,103 @@ pub struct BusDeviceFunction {
 impl BusDeviceFunction {
     pub const FORMAT: &str = "[[[[<domain>]:]<bus>]:][<slot>][.[<func>]]";
 
+    pub fn new(domain: u16, bus: u8, device: u8, function: u8) -> Self {
+        BusDeviceFunction {
+            domain: Some(domain),
+            bus: Some(bus),
+            device: Some(device),
+            function: Some(function),
+        }
+    }
+
+    pub fn from_routing_id(domain: u16, routing_id: u16) -> Self {
+        Self::new(
+            domain,
+            (routing_id >> 8) as u8,
+            ((routing_id >> 3) & 0x1f) as u8,
+            (routing_id & 0x7) as u8,
+        )
+    }
+
+    /// An Alternative Routing-ID Interpretation function, where the device number is always 0
+    /// and the function number takes the 8 bits below the bus number.
+    pub fn new_ari(domain: u16, bus: u8, function: u8) -> Self {
+        Self::new(domain, bus, 0, function)
+    }
+
+    pub fn from_ari_routing_id(domain: u16, routing_id: u16) -> Self {
+        Self::new_ari(domain, (routing_id >> 8) as u8, routing_id as u8)
+    }
+
+    pub fn is_ari(&self) -> bool {
+        self.device == Some(0) && self.function.is_some_and(|function| function > 0x7)
+    }
+
+    /// Returns the same function with the device and function numbers merged into an ARI
+    /// function number.
+    pub fn to_ari(&self) -> Self {
+        match (self.device, self.function) {
+            (Some(device), Some(function)) if !self.is_ari() => BusDeviceFunction {
+                device: Some(0),
+                function: Some((device & 0x1f) << 3 | function & 0x7),
+                ..*self
+            },
+            _ => *self,
+        }
+    }
+
+    /// Returns the same function with a conventional device and function number, which is how
+    /// the kernel names ARI functions.
+    pub fn to_conventional(&self) -> Self {
+        match self.function {
+            Some(function) if self.is_ari() => BusDeviceFunction {
+                device: Some(function >> 3),
+                function: Some(function & 0x7),
+                ..*self
+            },
+            _ => *self,
+        }
+    }
+
+    fn fields_eq(&self, other: &Self) -> bool {
+        (self.domain.is_none() || other.domain.is_none() || self.domain == other.domain)
+            && (self.bus.is_none() || other.bus.is_none() || self.bus == other.bus)
+            && (self.device.is_none() || other.device.is_none() || self.device == other.device)
+            && (self.function.is_none()
+                || other.function.is_none()
+                || self.function == other.function)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.bus.is_some() && self.device.is_some() && self.function.is_some()
+    }
+
+    pub fn domain(&self) -> Option<u16> {
+        self.domain
+    }
+
     pub fn bus(&self) -> Option<u8> {
         self.bus
     }
 
+    pub fn device(&self) -> Option<u8> {
+        self.device
+    }
+
+    pub fn function(&self) -> Option<u8> {
+        self.function
+    }
+
+    pub fn routing_id(&self) -> Option<u16> {
+        let bdf = self.to_conventional();
+
+        Some(
+            (bdf.bus? as u16) << 8
+                | ((bdf.device? as u16) & 0x1f) << 3
+                | (bdf.function? as u16) & 0x7,
+        )
+    }
+
     pub fn bdf_string(&self, always_domain: bool) -> String {
         let domain = match self.domain {
             Some(domain) => {
@@ -51,7 +144,7 @@ impl BusDeviceFunction {
     }
 
     pub fn canonical_bdf_string(&self) -> String {
-        self.bdf_string(true)
+        self.to_conventional().bdf_string(true)
     }
 
     fn from_str(s: &str) -> Result<Self> {
@@ -103,19 +196,13 @@ impl BusDeviceFunction {
 }
 
 impl PartialEq for BusDeviceFunction {
+    /// Partial addresses match the name as it is printed, complete addresses also match an ARI
+    /// function by its conventional name.
     fn eq(&self, other: &Self) -> bool {
-        let eq = (self.domain.is_none()
-            || other.domain.is_none()
-            || self.domain.unwrap() == other.domain.unwrap())
-            && (self.bus.is_none()
-                || other.bus.is_none()
-                || self.bus.unwrap() == other.bus.unwrap())
-            && (self.device.is_none()
-                || other.device.is_none()
-                || self.device.unwrap() == other.device.unwrap())
-            && (self.function.is_none()
-                || other.function.is_none()
-                || self.function.unwrap() == other.function.unwrap());
+        let eq = self.fields_eq(other)
+            || (self.is_complete()
+                && other.is_complete()
+                && self.to_conventional().fields_eq(&other.to_conventional()));
 
         trace!(
             target: "bdf",
@@ -240,6 +327,56 @@ mod tests {
         );
     }
     #[test]
+    fn test_routing_id() {
+        let bdf = BusDeviceFunction::from_str("0001:3b:1f.7").unwrap();
+        assert_eq!(bdf.routing_id(), Some(0x3bff));
+        assert_eq!(
+            BusDeviceFunction::from_routing_id(0x0001, 0x3bff),
+            BusDeviceFunction {
+                domain: Some(0x0001),
+                bus: Some(0x3b),
+                device: Some(0x1f),
+                function: Some(0x7),
+            }
+        );
+        assert_eq!(
+            BusDeviceFunction::from_str("1f.7").unwrap().routing_id(),
+            None
+        );
+    }
+    #[test]
+    fn test_ari() {
+        let bdf = BusDeviceFunction::from_ari_routing_id(0x0000, 0x0381);
+        assert!(bdf.is_ari());
+        assert_eq!(bdf.to_string(), "03:00.81");
+        assert_eq!(bdf.canonical_bdf_string(), "0000:03:10.1");
+        assert_eq!(bdf.routing_id(), Some(0x0381));
+        assert_eq!(bdf, BusDeviceFunction::from_str("03:10.1").unwrap());
+        assert_eq!(
+            BusDeviceFunction::from_str("0000:03:10.1")
+                .unwrap()
+                .to_ari(),
+            BusDeviceFunction {
+                domain: Some(0x0000),
+                bus: Some(0x03),
+                device: Some(0x00),
+                function: Some(0x81),
+            }
+        );
+        assert!(!BusDeviceFunction::new_ari(0x0000, 0x03, 0x7).is_ari());
+    }
+    #[test]
+    fn test_ari_slot_filter() {
+        let bdf = BusDeviceFunction::from_ari_routing_id(0x0000, 0x0381);
+
+        for slot in ["03:00", ".81", "03:00.81", "0000:03:00.81", "03:10.1"] {
+            assert_eq!(BusDeviceFunction::from_str(slot).unwrap(), bdf, "{}", slot);
+        }
+        for slot in ["03:10", ".1", "03:00.1"] {
+            assert_ne!(BusDeviceFunction::from_str(slot).unwrap(), bdf, "{}", slot);
+        }
+    }
+    #[test]
     fn test_string_format() {
         assert_eq!(
             BusDeviceFunction {
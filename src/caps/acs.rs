@@ -0,0 +1,238 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::pci_express::{DevicePortType, PciExpressCapability};
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use crate::function::Function;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+use super::PathViolation;
+
+const ACS_CONTROLS: &[(&str, u8)] = &[
+    ("SrcValid", 0),
+    ("TransBlk", 1),
+    ("ReqRedir", 2),
+    ("CmpltRedir", 3),
+    ("UpstreamFwd", 4),
+    ("EgressCtrl", 5),
+    ("DirectTrans", 6),
+];
+
+pub struct AcsCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+
+    capability: u16,
+    control: u16,
+    egress_control_vector: Vec<u32>,
+}
+
+impl AcsCapability {
+    pub const SOURCE_VALIDATION: u16 = 1 << 0;
+    pub const TRANSLATION_BLOCKING: u16 = 1 << 1;
+    pub const REQUEST_REDIRECT: u16 = 1 << 2;
+    pub const COMPLETION_REDIRECT: u16 = 1 << 3;
+    pub const UPSTREAM_FORWARDING: u16 = 1 << 4;
+    pub const EGRESS_CONTROL: u16 = 1 << 5;
+    pub const DIRECT_TRANSLATED: u16 = 1 << 6;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<AcsCapability> {
+        let raw = access.read(offset as u64 + 4, 4)?;
+        let capability = BinaryParser::le16(&raw, 0..2)?;
+        let control = BinaryParser::le16(&raw, 2..4)?;
+
+        let mut egress_control_vector = vec![];
+        if capability & Self::EGRESS_CONTROL != 0 {
+            let size = match capability >> 8 {
+                0 => 256,
+                size => size as usize,
+            };
+            let raw = access.read(offset as u64 + 8, size.div_ceil(32) * 4)?;
+            for i in 0..size.div_ceil(32) {
+                egress_control_vector.push(BinaryParser::le32(&raw, i * 4..i * 4 + 4)?);
+            }
+        }
+
+        Ok(AcsCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capability,
+            control,
+            egress_control_vector,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<AcsCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_extended(0x000d)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn capability(&self) -> u16 {
+        self.capability
+    }
+
+    pub fn control(&self) -> u16 {
+        self.control
+    }
+
+    pub fn egress_control_vector(&self) -> &[u32] {
+        &self.egress_control_vector
+    }
+
+    /// Returns the names of the controls in `required` that are implemented but not enabled.
+    /// Controls the function does not implement do not apply to it and are not reported.
+    pub fn missing_controls(&self, required: u16) -> Vec<&'static str> {
+        let missing = required & self.capability & !self.control;
+
+        ACS_CONTROLS
+            .iter()
+            .filter(|(_, bit)| missing & (1 << bit) != 0)
+            .map(|(name, _)| *name)
+            .collect()
+    }
+}
+
+impl Capability for AcsCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Access Control Services\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tACSCap:\t{}\n",
+                Flags::new(self.capability.into(), ACS_CONTROLS)
+            );
+            text += &format!(
+                "\t\tACSCtl:\t{}\n",
+                Flags::new(self.control.into(), ACS_CONTROLS)
+            );
+
+            if !self.egress_control_vector.is_empty() {
+                let vector: Vec<_> = self
+                    .egress_control_vector
+                    .iter()
+                    .map(|v| format!("{:0>8x}", v))
+                    .collect();
+                text += &format!("\t\tEgressCtlVector: {}\n", vector.join(" "));
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for AcsCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+/// Checks the ACS controls along `path`, which starts with the function to be assigned and
+/// continues with its upstream bridges up to and including the root port. The path is isolated
+/// enough for device assignment when no violations are returned.
+///
+/// Root ports and switch downstream ports must enable source validation, request and
+/// completion redirect and upstream forwarding. Multi-function devices must redirect
+/// peer-to-peer requests and completions between their functions.
+pub fn path_violations(path: &[Function]) -> Result<Vec<PathViolation>> {
+    let mut violations = vec![];
+
+    for (i, function) in path.iter().enumerate() {
+        let express = PciExpressCapability::find(&function.access())?;
+        let port_type = match &express {
+            Some(express) => Some(express.device_port_type()?),
+            None => None,
+        };
+
+        let required = match port_type {
+            None => {
+                violations.push(PathViolation {
+                    bdf: *function.bdf(),
+                    missing: vec!["PCIe"],
+                });
+                continue;
+            }
+            Some(DevicePortType::RootPort) | Some(DevicePortType::DownstreamPort) => {
+                AcsCapability::SOURCE_VALIDATION
+                    | AcsCapability::REQUEST_REDIRECT
+                    | AcsCapability::COMPLETION_REDIRECT
+                    | AcsCapability::UPSTREAM_FORWARDING
+            }
+            Some(_) if i == 0 && function.is_multifunction()? => {
+                AcsCapability::REQUEST_REDIRECT | AcsCapability::COMPLETION_REDIRECT
+            }
+            Some(_) => continue,
+        };
+
+        let missing = match AcsCapability::find(&function.access())? {
+            Some(acs) => acs.missing_controls(required),
+            None => vec!["ACS"],
+        };
+        if !missing.is_empty() {
+            violations.push(PathViolation {
+                bdf: *function.bdf(),
+                missing,
+            });
+        }
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+    use crate::bdf::BusDeviceFunction;
+    use crate::kernel::Kernel;
+    use std::str::FromStr;
+
+    fn function(bdf: &str, port_type: u8, acs: Option<(u16, u16)>) -> Function {
+        let mut dump = vec![0; 0x1000];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x40;
+        dump[0x40..0x44].copy_from_slice(&[0x10, 0x00, 0x02 | port_type << 4, 0x00]);
+        if let Some((capability, control)) = acs {
+            dump[0x100..0x104].copy_from_slice(&0x0001_000du32.to_le_bytes());
+            dump[0x104..0x106].copy_from_slice(&capability.to_le_bytes());
+            dump[0x106..0x108].copy_from_slice(&control.to_le_bytes());
+        }
+
+        Function::new(
+            BusDeviceFunction::from_str(bdf).unwrap(),
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            Kernel,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_path_violations() {
+        let path = [
+            function("0000:03:00.0", 0x0, None),
+            function("0000:02:01.0", 0x6, Some((0x001f, 0x001b))),
+            function("0000:01:00.0", 0x5, None),
+            function("0000:00:1c.0", 0x4, None),
+        ];
+
+        let violations = path_violations(&path).unwrap();
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[0].to_string(), "02:01.0: missing ReqRedir");
+        assert_eq!(violations[1].to_string(), "00:1c.0: missing ACS");
+    }
+}
@@ -0,0 +1,288 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::pci_express::{DevicePortType, PciExpressCapability};
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+const UNCORRECTABLE_ERRORS: &[(&str, u8)] = &[
+    ("DLP", 4),
+    ("SDES", 5),
+    ("TLP", 12),
+    ("FCP", 13),
+    ("CmpltTO", 14),
+    ("CmpltAbrt", 15),
+    ("UnxCmplt", 16),
+    ("RxOF", 17),
+    ("MalfTLP", 18),
+    ("ECRC", 19),
+    ("UnsupReq", 20),
+    ("ACSViol", 21),
+    ("UncorrIntErr", 22),
+    ("BlockedTLP", 23),
+    ("AtomicOpBlocked", 24),
+    ("TLPBlockedErr", 25),
+    ("PoisonTLPBlocked", 26),
+];
+
+const CORRECTABLE_ERRORS: &[(&str, u8)] = &[
+    ("RxErr", 0),
+    ("BadTLP", 6),
+    ("BadDLLP", 7),
+    ("Rollover", 8),
+    ("Timeout", 12),
+    ("AdvNonFatalErr", 13),
+    ("CorrIntErr", 14),
+    ("HeaderOF", 15),
+];
+
+pub struct AdvancedErrorReportingCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    port_type: Option<DevicePortType>,
+    raw: Vec<u8>,
+}
+
+impl AdvancedErrorReportingCapability {
+    const LENGTH: usize = 0x38;
+
+    pub fn new(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+    ) -> Result<AdvancedErrorReportingCapability> {
+        let port_type = match PciExpressCapability::find(&access)? {
+            Some(express) => Some(express.device_port_type()?),
+            None => None,
+        };
+
+        Ok(AdvancedErrorReportingCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            raw: access.read(offset.into(), Self::LENGTH)?,
+            _access: access,
+            offset,
+            port_type,
+        })
+    }
+
+    pub fn uncorrectable_status(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x04..0x08)
+    }
+
+    pub fn uncorrectable_mask(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x08..0x0c)
+    }
+
+    pub fn uncorrectable_severity(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x0c..0x10)
+    }
+
+    pub fn correctable_status(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x10..0x14)
+    }
+
+    pub fn correctable_mask(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x14..0x18)
+    }
+
+    pub fn capabilities_and_control(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x18..0x1c)
+    }
+
+    pub fn header_log(&self) -> Result<[u32; 4]> {
+        Ok([
+            BinaryParser::le32(&self.raw, 0x1c..0x20)?,
+            BinaryParser::le32(&self.raw, 0x20..0x24)?,
+            BinaryParser::le32(&self.raw, 0x24..0x28)?,
+            BinaryParser::le32(&self.raw, 0x28..0x2c)?,
+        ])
+    }
+
+    pub fn root_error_command(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x2c..0x30)
+    }
+
+    pub fn root_error_status(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x30..0x34)
+    }
+
+    pub fn error_source_identification(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x34..0x38)
+    }
+
+    pub fn uncorrectable_status_flags(&self) -> Result<Flags> {
+        Ok(Flags::new(
+            self.uncorrectable_status()?,
+            UNCORRECTABLE_ERRORS,
+        ))
+    }
+
+    pub fn uncorrectable_mask_flags(&self) -> Result<Flags> {
+        Ok(Flags::new(self.uncorrectable_mask()?, UNCORRECTABLE_ERRORS))
+    }
+
+    pub fn uncorrectable_severity_flags(&self) -> Result<Flags> {
+        Ok(Flags::new(
+            self.uncorrectable_severity()?,
+            UNCORRECTABLE_ERRORS,
+        ))
+    }
+
+    pub fn correctable_status_flags(&self) -> Result<Flags> {
+        Ok(Flags::new(self.correctable_status()?, CORRECTABLE_ERRORS))
+    }
+
+    pub fn correctable_mask_flags(&self) -> Result<Flags> {
+        Ok(Flags::new(self.correctable_mask()?, CORRECTABLE_ERRORS))
+    }
+
+    fn has_root_registers(&self) -> bool {
+        self.port_type
+            .map(|t| t.has_root_registers())
+            .unwrap_or_default()
+    }
+
+    fn root_string(&self) -> Result<String> {
+        let mut text = format!(
+            "\t\tRootCmd: {}\n",
+            Flags::new(
+                self.root_error_command()?,
+                &[("CERptEn", 0), ("NFERptEn", 1), ("FERptEn", 2)]
+            )
+        );
+
+        let sta = self.root_error_status()?;
+        text += &format!(
+            "\t\tRootSta: {}\n",
+            Flags::new(
+                sta,
+                &[
+                    ("CERcvd", 0),
+                    ("MultCERcvd", 1),
+                    ("UERcvd", 2),
+                    ("MultUERcvd", 3)
+                ]
+            )
+        );
+        text += &format!(
+            "\t\t\t {} IntMsg {}\n",
+            Flags::new(
+                sta,
+                &[("FirstFatal", 4), ("NonFatalMsg", 5), ("FatalMsg", 6)]
+            ),
+            sta >> 27
+        );
+
+        let source = self.error_source_identification()?;
+        text += &format!(
+            "\t\tErrorSrc: ERR_COR: {:0>4x} ERR_FATAL/NONFATAL: {:0>4x}\n",
+            source & 0xffff,
+            source >> 16
+        );
+
+        Ok(text)
+    }
+}
+
+impl Capability for AdvancedErrorReportingCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Advanced Error Reporting\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!("\t\tUESta:\t{}\n", self.uncorrectable_status_flags()?);
+            text += &format!("\t\tUEMsk:\t{}\n", self.uncorrectable_mask_flags()?);
+            text += &format!("\t\tUESvrt:\t{}\n", self.uncorrectable_severity_flags()?);
+            text += &format!("\t\tCESta:\t{}\n", self.correctable_status_flags()?);
+            text += &format!("\t\tCEMsk:\t{}\n", self.correctable_mask_flags()?);
+
+            let cap = self.capabilities_and_control()?;
+            text += &format!(
+                "\t\tAERCap:\tFirst Error Pointer: {:0>2x}, {}\n",
+                cap & 0x1f,
+                Flags::new(
+                    cap,
+                    &[
+                        ("ECRCGenCap", 5),
+                        ("ECRCGenEn", 6),
+                        ("ECRCChkCap", 7),
+                        ("ECRCChkEn", 8)
+                    ]
+                )
+            );
+            text += &format!(
+                "\t\t\t{}\n",
+                Flags::new(
+                    cap,
+                    &[
+                        ("MultHdrRecCap", 9),
+                        ("MultHdrRecEn", 10),
+                        ("TLPPfxPres", 11),
+                        ("HdrLogCap", 12)
+                    ]
+                )
+            );
+
+            let log = self.header_log()?;
+            text += &format!(
+                "\t\tHeaderLog: {:0>8x} {:0>8x} {:0>8x} {:0>8x}\n",
+                log[0], log[1], log[2], log[3]
+            );
+
+            if self.has_root_registers() {
+                text += &self.root_string()?;
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for AdvancedErrorReportingCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_root_port() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x34] = 0x40;
+        dump[0x40..0x44].copy_from_slice(&[0x10, 0x00, 0x42, 0x00]);
+        dump[0x100..0x104].copy_from_slice(&0x0002_0001u32.to_le_bytes());
+        dump[0x104..0x108].copy_from_slice(&(1u32 << 14).to_le_bytes());
+        dump[0x130..0x134].copy_from_slice(&0x0000_0014u32.to_le_bytes());
+        dump[0x134..0x138].copy_from_slice(&0x0100_0000u32.to_le_bytes());
+
+        let cap =
+            AdvancedErrorReportingCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x100)
+                .unwrap();
+
+        assert_eq!(cap.version().unwrap(), Some(2));
+        assert_eq!(
+            cap.uncorrectable_status_flags().unwrap().get("CmpltTO"),
+            Some(true)
+        );
+
+        let text = cap.cap_string(2).unwrap();
+        assert!(text.contains("\t\tRootSta: CERcvd- MultCERcvd- UERcvd+ MultUERcvd-\n"));
+        assert!(text.contains("\t\t\t FirstFatal+ NonFatalMsg- FatalMsg- IntMsg 0\n"));
+        assert!(text.contains("ERR_COR: 0000 ERR_FATAL/NONFATAL: 0100"));
+    }
+}
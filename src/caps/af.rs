@@ -0,0 +1,132 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flag;
+
+/// The Advanced Features capability, which gives conventional PCI functions an FLR.
+pub struct AdvancedFeaturesCapability {
+    access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    capabilities: u8,
+}
+
+impl AdvancedFeaturesCapability {
+    pub const CONTROL: u64 = 0x04;
+    pub const STATUS: u64 = 0x05;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<AdvancedFeaturesCapability> {
+        let raw = access.read(offset as u64 + 3, 1)?;
+
+        Ok(AdvancedFeaturesCapability {
+            access,
+            offset,
+            capabilities: BinaryParser::le8(&raw, 0..1)?,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<AdvancedFeaturesCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_trad(0x13)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn transactions_pending_capable(&self) -> bool {
+        self.capabilities & 0x1 != 0
+    }
+
+    pub fn function_level_reset_capable(&self) -> bool {
+        self.capabilities & 0x2 != 0
+    }
+
+    pub fn control(&self) -> Result<u8> {
+        BinaryParser::le8(
+            &self.access.read(self.offset as u64 + Self::CONTROL, 1)?,
+            0..1,
+        )
+    }
+
+    pub fn status(&self) -> Result<u8> {
+        BinaryParser::le8(
+            &self.access.read(self.offset as u64 + Self::STATUS, 1)?,
+            0..1,
+        )
+    }
+
+    pub fn transactions_pending(&self) -> Result<bool> {
+        Ok(self.status()? & 0x1 != 0)
+    }
+
+    /// Starts a Function Level Reset, the function must be quiesced by the caller.
+    pub fn initiate_function_level_reset(&self) -> Result<()> {
+        self.access
+            .write(self.offset as u64 + Self::CONTROL, &[0x1])?;
+        Ok(())
+    }
+}
+
+impl Capability for AdvancedFeaturesCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "PCI Advanced Features".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\n\t\tAFCap: {} {}",
+                Flag::new("TP", self.transactions_pending_capable()),
+                Flag::new("FLR", self.function_level_reset_capable())
+            );
+            text += &format!(
+                "\n\t\tAFCtrl: {}",
+                Flag::new("FLR", self.control()? & 0x1 != 0)
+            );
+            text += &format!(
+                "\n\t\tAFStatus: {}",
+                Flag::new("TP", self.transactions_pending()?)
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for AdvancedFeaturesCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_af() {
+        let mut dump = vec![0; 0x100];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x50;
+        dump[0x50..0x56].copy_from_slice(&[0x13, 0x00, 0x06, 0x03, 0x00, 0x01]);
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(DumpAccess::new(&dump)));
+        let cap = AdvancedFeaturesCapability::find(&access).unwrap().unwrap();
+
+        assert!(cap.function_level_reset_capable());
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "PCI Advanced Features\n\
+             \t\tAFCap: TP+ FLR+\n\
+             \t\tAFCtrl: FLR-\n\
+             \t\tAFStatus: TP+"
+        );
+    }
+}
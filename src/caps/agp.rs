@@ -0,0 +1,148 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct AgpCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    version: u8,
+    status: u32,
+    command: u32,
+}
+
+impl AgpCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<AgpCapability> {
+        let raw = access.read(offset.into(), 12)?;
+
+        Ok(AgpCapability {
+            _access: access,
+            offset,
+            version: BinaryParser::le8(&raw, 0x02..0x03)?,
+            status: BinaryParser::le32(&raw, 0x04..0x08)?,
+            command: BinaryParser::le32(&raw, 0x08..0x0c)?,
+        })
+    }
+
+    pub fn major(&self) -> u8 {
+        self.version >> 4
+    }
+
+    pub fn minor(&self) -> u8 {
+        self.version & 0xf
+    }
+
+    pub fn status(&self) -> u32 {
+        self.status
+    }
+
+    pub fn command(&self) -> u32 {
+        self.command
+    }
+
+    /// Whether the status register is in AGP3 mode, which changes the meaning of the rate bits.
+    pub fn agp3(&self) -> bool {
+        self.major() >= 3 && self.status & (1 << 3) != 0
+    }
+
+    fn rate(register: u32, agp3: bool) -> String {
+        let rates: Vec<_> = (0..3)
+            .filter(|bit| register & (1 << bit) != 0)
+            .map(|bit| format!("x{}", 1 << (bit + 2 * agp3 as u32)))
+            .collect();
+
+        match rates.is_empty() {
+            true => "<none>".to_string(),
+            false => rates.join(","),
+        }
+    }
+}
+
+impl Capability for AgpCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!("AGP version {:x}.{:x}\n", self.major(), self.minor());
+
+        if verbosity >= 2 {
+            let status = self.status;
+            text += &format!(
+                "\t\tStatus: RQ={} {} ArqSz={} Cal={} {} Rate={}\n",
+                (status >> 24) + 1,
+                Flags::new(status, &[("Iso", 16)]),
+                (status >> 13) & 0x7,
+                (status >> 10) & 0x7,
+                Flags::new(
+                    status,
+                    &[
+                        ("SBA", 9),
+                        ("ITACoh", 8),
+                        ("GART64", 7),
+                        ("HTrans", 6),
+                        ("64bit", 5),
+                        ("FW", 4),
+                        ("AGP3", 3)
+                    ]
+                ),
+                Self::rate(status, self.agp3())
+            );
+
+            let command = self.command;
+            text += &format!(
+                "\t\tCommand: RQ={} ArqSz={} Cal={} {} Rate={}\n",
+                (command >> 24) + 1,
+                (command >> 13) & 0x7,
+                (command >> 10) & 0x7,
+                Flags::new(
+                    command,
+                    &[
+                        ("SBA", 9),
+                        ("AGP", 8),
+                        ("GART64", 7),
+                        ("64bit", 5),
+                        ("FW", 4)
+                    ]
+                ),
+                Self::rate(command, self.agp3())
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for AgpCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_agp3() {
+        let mut dump = vec![0; 0x100];
+        dump[0x58..0x5c].copy_from_slice(&0x0030_0002u32.to_le_bytes());
+        dump[0x5c..0x60].copy_from_slice(&0x1f00_4a1bu32.to_le_bytes());
+        dump[0x60..0x64].copy_from_slice(&0x1f00_0302u32.to_le_bytes());
+
+        let cap = AgpCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x58).unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "AGP version 3.0\n\
+             \t\tStatus: RQ=32 Iso- ArqSz=2 Cal=2 SBA+ ITACoh- GART64- HTrans- 64bit- FW+ AGP3+ Rate=x4,x8\n\
+             \t\tCommand: RQ=32 ArqSz=0 Cal=0 SBA+ AGP+ GART64- 64bit- FW- Rate=x8"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct AriCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    capabilities: u16,
+    control: u16,
+}
+
+impl AriCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<AriCapability> {
+        let raw = access.read(offset as u64 + 4, 4)?;
+
+        Ok(AriCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities: BinaryParser::le16(&raw, 0..2)?,
+            control: BinaryParser::le16(&raw, 2..4)?,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<AriCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_extended(0x000e)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn capabilities(&self) -> u16 {
+        self.capabilities
+    }
+
+    pub fn control(&self) -> u16 {
+        self.control
+    }
+
+    pub fn mfvc_function_groups(&self) -> bool {
+        self.capabilities & (1 << 0) != 0
+    }
+
+    pub fn acs_function_groups(&self) -> bool {
+        self.capabilities & (1 << 1) != 0
+    }
+
+    /// Function number of the next function of the device, where 0 ends the list.
+    pub fn next_function(&self) -> u8 {
+        (self.capabilities >> 8) as u8
+    }
+
+    pub fn function_group(&self) -> u8 {
+        ((self.control >> 4) & 0x7) as u8
+    }
+}
+
+impl Capability for AriCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Alternative Routing-ID Interpretation (ARI)\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tARICap:\t{}, Next Function: {}\n",
+                Flags::new(self.capabilities.into(), &[("MFVC", 0), ("ACS", 1)]),
+                self.next_function()
+            );
+            text += &format!(
+                "\t\tARICtl:\t{}, Function Group: {}\n",
+                Flags::new(self.control.into(), &[("MFVC", 0), ("ACS", 1)]),
+                self.function_group()
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for AriCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_ari() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x100..0x104].copy_from_slice(&0x1501_000eu32.to_le_bytes());
+        dump[0x104..0x106].copy_from_slice(&0x0802u16.to_le_bytes());
+        dump[0x106..0x108].copy_from_slice(&0x0032u16.to_le_bytes());
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(DumpAccess::new(&dump)));
+        let cap = AriCapability::find(&access).unwrap().unwrap();
+
+        assert_eq!(cap.next_function(), 8);
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Alternative Routing-ID Interpretation (ARI)\n\
+             \t\tARICap:\tMFVC- ACS+, Next Function: 8\n\
+             \t\tARICtl:\tMFVC- ACS+, Function Group: 3"
+        );
+    }
+}
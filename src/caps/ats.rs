@@ -0,0 +1,129 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct AddressTranslationServicesCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    capabilities: u16,
+    control: u16,
+}
+
+impl AddressTranslationServicesCapability {
+    pub fn new(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+    ) -> Result<AddressTranslationServicesCapability> {
+        let raw = access.read(offset as u64 + 4, 4)?;
+
+        Ok(AddressTranslationServicesCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities: BinaryParser::le16(&raw, 0..2)?,
+            control: BinaryParser::le16(&raw, 2..4)?,
+        })
+    }
+
+    pub fn capabilities(&self) -> u16 {
+        self.capabilities
+    }
+
+    pub fn control(&self) -> u16 {
+        self.control
+    }
+
+    /// Number of invalidate requests the function can queue, where the encoded 0 means 32.
+    pub fn invalidate_queue_depth(&self) -> u8 {
+        match self.capabilities & 0x1f {
+            0 => 32,
+            depth => depth as u8,
+        }
+    }
+
+    pub fn page_aligned_request(&self) -> bool {
+        self.capabilities & (1 << 5) != 0
+    }
+
+    /// Smallest Translation Unit as a power of two of 4096 bytes.
+    pub fn smallest_translation_unit(&self) -> u8 {
+        (self.control & 0x1f) as u8
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.control & (1 << 15) != 0
+    }
+}
+
+impl Capability for AddressTranslationServicesCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Address Translation Service (ATS)\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tATSCap:\tInvalidate Queue Depth: {:0>2x}, {}\n",
+                self.capabilities & 0x1f,
+                Flags::new(
+                    self.capabilities.into(),
+                    &[("PageAlignedReq", 5), ("GlobalInvalidate", 6)]
+                )
+            );
+            text += &format!(
+                "\t\tATSCtl:\t{}, Smallest Translation Unit: {:0>2x}\n",
+                Flags::new(self.control.into(), &[("Enable", 15)]),
+                self.smallest_translation_unit()
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for AddressTranslationServicesCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_ats() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x180..0x184].copy_from_slice(&0x0001_000fu32.to_le_bytes());
+        dump[0x184..0x186].copy_from_slice(&0x0020u16.to_le_bytes());
+        dump[0x186..0x188].copy_from_slice(&0x8001u16.to_le_bytes());
+
+        let cap = AddressTranslationServicesCapability::new(
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            0x180,
+        )
+        .unwrap();
+
+        assert_eq!(cap.invalidate_queue_depth(), 32);
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Address Translation Service (ATS)\n\
+             \t\tATSCap:\tInvalidate Queue Depth: 00, PageAlignedReq+ GlobalInvalidate-\n\
+             \t\tATSCtl:\tEnable+, Smallest Translation Unit: 01"
+        );
+    }
+}
@@ -0,0 +1,421 @@
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use std::fmt::Display;
+
+use super::vendor_specific::DesignatedVendorSpecificCapability;
+use super::Flags;
+
+pub const CXL_VENDOR_ID: u16 = 0x1e98;
+
+pub const CXL_DEVICE_DVSEC_ID: u16 = 0x0000;
+pub const CXL_PORT_DVSEC_ID: u16 = 0x0003;
+pub const CXL_GPF_PORT_DVSEC_ID: u16 = 0x0004;
+pub const CXL_GPF_DEVICE_DVSEC_ID: u16 = 0x0005;
+pub const CXL_FLEX_BUS_PORT_DVSEC_ID: u16 = 0x0007;
+pub const CXL_REGISTER_LOCATOR_DVSEC_ID: u16 = 0x0008;
+
+// Register offsets in the CXL specification are relative to the start of the DVSEC, while the
+// DVSEC body starts right after the two DVSEC headers.
+const BODY_OFFSET: usize = 0xa;
+
+fn le16(dvsec: &DesignatedVendorSpecificCapability, offset: usize) -> Result<u16> {
+    BinaryParser::le16(dvsec.body(), offset - BODY_OFFSET..offset - BODY_OFFSET + 2)
+}
+
+fn le32(dvsec: &DesignatedVendorSpecificCapability, offset: usize) -> Result<u32> {
+    BinaryParser::le32(dvsec.body(), offset - BODY_OFFSET..offset - BODY_OFFSET + 4)
+}
+
+pub struct CxlRange {
+    size: u64,
+    base: u64,
+    flags: u32,
+}
+
+impl CxlRange {
+    pub fn new(size_high: u32, size_low: u32, base_high: u32, base_low: u32) -> CxlRange {
+        CxlRange {
+            size: (size_high as u64) << 32 | (size_low & 0xf000_0000) as u64,
+            base: (base_high as u64) << 32 | (base_low & 0xf000_0000) as u64,
+            flags: size_low,
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    pub fn valid(&self) -> bool {
+        self.flags & (1 << 0) != 0
+    }
+
+    pub fn active(&self) -> bool {
+        self.flags & (1 << 1) != 0
+    }
+
+    fn media_type(&self) -> &'static str {
+        match (self.flags >> 2) & 0x7 {
+            0 => "Volatile",
+            1 => "Non-volatile",
+            2 => "CDAT",
+            _ => "Reserved",
+        }
+    }
+
+    fn memory_class(&self) -> &'static str {
+        match (self.flags >> 5) & 0x7 {
+            0 => "DRAM",
+            1 => "Storage",
+            2 => "CDAT",
+            _ => "Reserved",
+        }
+    }
+
+    fn active_timeout(&self) -> &'static str {
+        match (self.flags >> 13) & 0x7 {
+            0 => "1s",
+            1 => "4s",
+            2 => "16s",
+            3 => "64s",
+            4 => "256s",
+            _ => "Reserved",
+        }
+    }
+}
+
+impl Display for CxlRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:0>16x}-{:0>16x}\n\t\t\t{} Type={} Class={} interleave={} timeout={}",
+            self.base,
+            (self.base + self.size).wrapping_sub(1),
+            Flags::new(self.flags, &[("Valid", 0), ("Active", 1)]),
+            self.media_type(),
+            self.memory_class(),
+            (self.flags >> 8) & 0x1f,
+            self.active_timeout()
+        )
+    }
+}
+
+pub fn device_ranges(dvsec: &DesignatedVendorSpecificCapability) -> Result<Vec<CxlRange>> {
+    let mut ranges = vec![];
+
+    for offset in [0x18, 0x28] {
+        ranges.push(CxlRange::new(
+            le32(dvsec, offset)?,
+            le32(dvsec, offset + 0x4)?,
+            le32(dvsec, offset + 0x8)?,
+            le32(dvsec, offset + 0xc)?,
+        ));
+    }
+
+    Ok(ranges)
+}
+
+pub fn device_string(dvsec: &DesignatedVendorSpecificCapability, _verbosity: u8) -> Result<String> {
+    let cap = le16(dvsec, 0x0a)?;
+    let mut text = format!(
+        "\t\tCXLCap:\t{} HDMCount {} {}\n",
+        Flags::new(
+            cap.into(),
+            &[("Cache", 0), ("IO", 1), ("Mem", 2), ("MemHWInit", 3)]
+        ),
+        (cap >> 4) & 0x3,
+        Flags::new(cap.into(), &[("Viral", 14)])
+    );
+
+    let ctl = le16(dvsec, 0x0c)?;
+    text += &format!(
+        "\t\tCXLCtl:\t{} CacheSFCov {} CacheSFGran {} {}\n",
+        Flags::new(ctl.into(), &[("Cache", 0), ("IO", 1), ("Mem", 2)]),
+        (ctl >> 3) & 0x1f,
+        (ctl >> 8) & 0x7,
+        Flags::new(ctl.into(), &[("CacheClean", 11), ("Viral", 14)])
+    );
+
+    let sta = le16(dvsec, 0x0e)?;
+    text += &format!(
+        "\t\tCXLSta:\t{}\n",
+        Flags::new(sta.into(), &[("Viral", 14)])
+    );
+
+    if dvsec.revision() >= 1 {
+        let ctl2 = le16(dvsec, 0x10)?;
+        text += &format!(
+            "\t\tCXLCtl2:\t{}\n",
+            Flags::new(
+                ctl2.into(),
+                &[
+                    ("DisableCaching", 0),
+                    ("InitCacheWB&Inval", 1),
+                    ("InitRst", 2),
+                    ("RstMemClrEn", 3)
+                ]
+            )
+        );
+
+        let sta2 = le16(dvsec, 0x12)?;
+        text += &format!(
+            "\t\tCXLSta2:\t{}\n",
+            Flags::new(
+                sta2.into(),
+                &[
+                    ("CacheInvalid", 0),
+                    ("ResetComplete", 1),
+                    ("ResetError", 2),
+                    ("PMComplete", 15)
+                ]
+            )
+        );
+
+        let lock = le16(dvsec, 0x14)?;
+        text += &format!(
+            "\t\tCXLLock:\t{}\n",
+            Flags::new(lock.into(), &[("LOCK", 0)])
+        );
+    }
+
+    for (i, range) in device_ranges(dvsec)?.iter().enumerate() {
+        text += &format!("\t\tRange{}: {}\n", i + 1, range);
+    }
+
+    Ok(text)
+}
+
+pub fn port_string(dvsec: &DesignatedVendorSpecificCapability, _verbosity: u8) -> Result<String> {
+    if dvsec.length() < 0x28 {
+        return Ok(String::new());
+    }
+
+    let mut text = format!(
+        "\t\tCXLPortSta:\t{}\n",
+        Flags::new(le16(dvsec, 0x0a)?.into(), &[("PMComplete", 0)])
+    );
+
+    text += &format!(
+        "\t\tCXLPortCtl:\t{}\n",
+        Flags::new(
+            le16(dvsec, 0x0c)?.into(),
+            &[
+                ("UnmaskSBR", 0),
+                ("UnmaskLinkDisable", 1),
+                ("AltMem", 2),
+                ("AltBME", 3),
+                ("ViralEnable", 14)
+            ]
+        )
+    );
+
+    let bus = le16(dvsec, 0x0e)?;
+    text += &format!("\t\tAlternateBus:\t{:0>2x}-{:0>2x}\n", bus & 0xff, bus >> 8);
+    text += &format!(
+        "\t\tAlternateMem:\t{:0>4x}-{:0>4x}\n",
+        le16(dvsec, 0x10)?,
+        le16(dvsec, 0x12)?
+    );
+
+    Ok(text)
+}
+
+pub fn flex_bus_port_string(
+    dvsec: &DesignatedVendorSpecificCapability,
+    _verbosity: u8,
+) -> Result<String> {
+    let cap = le16(dvsec, 0x0a)?;
+    let mut text = format!(
+        "\t\tFBCap:\t{}\n",
+        Flags::new(
+            cap.into(),
+            &[
+                ("Cache", 0),
+                ("IO", 1),
+                ("Mem", 2),
+                ("68BFlit", 5),
+                ("MltLogDev", 6),
+                ("256BFlit", 13),
+                ("PBRFlit", 14)
+            ]
+        )
+    );
+
+    let ctl = le16(dvsec, 0x0c)?;
+    text += &format!(
+        "\t\tFBCtl:\t{}\n",
+        Flags::new(
+            ctl.into(),
+            &[
+                ("Cache", 0),
+                ("IO", 1),
+                ("Mem", 2),
+                ("SynHdrByp", 3),
+                ("DrftBuf", 4),
+                ("68BFlit", 5),
+                ("MltLogDev", 6),
+                ("RCD", 7),
+                ("Retimer1", 8),
+                ("Retimer2", 9),
+                ("256BFlit", 13),
+                ("PBRFlit", 14)
+            ]
+        )
+    );
+
+    let sta = le16(dvsec, 0x0e)?;
+    text += &format!(
+        "\t\tFBSta:\t{}\n",
+        Flags::new(
+            sta.into(),
+            &[
+                ("Cache", 0),
+                ("IO", 1),
+                ("Mem", 2),
+                ("SynHdrByp", 3),
+                ("DrftBuf", 4),
+                ("68BFlit", 5),
+                ("MltLogDev", 6),
+                ("256BFlit", 13),
+                ("PBRFlit", 14)
+            ]
+        )
+    );
+
+    Ok(text)
+}
+
+pub fn register_locator_string(
+    dvsec: &DesignatedVendorSpecificCapability,
+    _verbosity: u8,
+) -> Result<String> {
+    let mut text = String::new();
+
+    let entries = (dvsec.length() as usize).saturating_sub(0x0c) / 8;
+    for i in 0..entries {
+        let low = le32(dvsec, 0x0c + i * 8)?;
+        let high = le32(dvsec, 0x10 + i * 8)?;
+
+        text += &format!(
+            "\t\tBlock{}: BIR: bar{}, ID: {}, offset: {:0>16x}\n",
+            i + 1,
+            low & 0x7,
+            register_block((low >> 8) & 0xff),
+            (high as u64) << 32 | (low & 0xffff_0000) as u64
+        );
+    }
+
+    Ok(text)
+}
+
+pub fn gpf_port_string(
+    dvsec: &DesignatedVendorSpecificCapability,
+    _verbosity: u8,
+) -> Result<String> {
+    Ok(format!(
+        "\t\tGPF Phase 1 Timeout: {}\n\t\tGPF Phase 2 Timeout: {}\n",
+        gpf_time(le16(dvsec, 0x0c)?),
+        gpf_time(le16(dvsec, 0x0e)?)
+    ))
+}
+
+pub fn gpf_device_string(
+    dvsec: &DesignatedVendorSpecificCapability,
+    _verbosity: u8,
+) -> Result<String> {
+    Ok(format!(
+        "\t\tGPF Phase 2 Duration: {}\n\t\tGPF Phase 2 Power: {}mW\n",
+        gpf_time(le16(dvsec, 0x0a)?),
+        le32(dvsec, 0x0c)?
+    ))
+}
+
+fn register_block(id: u32) -> &'static str {
+    match id {
+        0 => "empty",
+        1 => "component registers",
+        2 => "BAR virtualization",
+        3 => "CXL device registers",
+        4 => "CPMU registers",
+        _ => "unknown",
+    }
+}
+
+fn gpf_time(register: u16) -> String {
+    let base = (register & 0xf) as u32;
+    let scale = ((register >> 8) & 0xf) as u32;
+    let unit = match scale / 3 {
+        0 => "us",
+        1 => "ms",
+        2 => "s",
+        _ => return "Reserved".to_string(),
+    };
+
+    format!("{}{}", base * 10u32.pow(scale % 3), unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+    use crate::access::Access;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_device_ranges() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x100..0x104].copy_from_slice(&0x0001_0023u32.to_le_bytes());
+        dump[0x104..0x108].copy_from_slice(&0x0381_1e98u32.to_le_bytes());
+        // CXLCap: IO+ Mem+ HDMCount 1
+        dump[0x10a..0x10c].copy_from_slice(&0x0016u16.to_le_bytes());
+        // Range1: 4GB at 0x10_0000_0000, valid and active
+        dump[0x118..0x11c].copy_from_slice(&0x0000_0001u32.to_le_bytes());
+        dump[0x11c..0x120].copy_from_slice(&0x0000_0003u32.to_le_bytes());
+        dump[0x120..0x124].copy_from_slice(&0x0000_0010u32.to_le_bytes());
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(DumpAccess::new(&dump)));
+        let dvsec =
+            DesignatedVendorSpecificCapability::with_decoder(access, 0x100, "CXL", device_string)
+                .unwrap();
+
+        let ranges = device_ranges(&dvsec).unwrap();
+        assert_eq!(ranges[0].size(), 0x1_0000_0000);
+        assert_eq!(ranges[0].base(), 0x10_0000_0000);
+        assert!(ranges[0].valid() && ranges[0].active());
+
+        let text = device_string(&dvsec, 2).unwrap();
+        assert!(text.starts_with("\t\tCXLCap:\tCache- IO+ Mem+ MemHWInit- HDMCount 1 Viral-\n"));
+        assert!(text.contains(
+            "\t\tRange1: 0000001000000000-00000010ffffffff\n\t\t\tValid+ Active+ Type=Volatile Class=DRAM interleave=0 timeout=1s\n"
+        ));
+    }
+
+    #[test]
+    fn test_port() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x100..0x104].copy_from_slice(&0x0001_0023u32.to_le_bytes());
+        dump[0x104..0x108].copy_from_slice(&0x0281_1e98u32.to_le_bytes());
+        dump[0x108..0x10a].copy_from_slice(&CXL_PORT_DVSEC_ID.to_le_bytes());
+        dump[0x10a..0x10c].copy_from_slice(&0x0001u16.to_le_bytes());
+        dump[0x10c..0x10e].copy_from_slice(&0x0005u16.to_le_bytes());
+        dump[0x10e..0x110].copy_from_slice(&0x0403u16.to_le_bytes());
+        dump[0x110..0x112].copy_from_slice(&0xa000u16.to_le_bytes());
+        dump[0x112..0x114].copy_from_slice(&0xa0f0u16.to_le_bytes());
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(DumpAccess::new(&dump)));
+        let dvsec =
+            DesignatedVendorSpecificCapability::with_decoder(access, 0x100, "CXL", port_string)
+                .unwrap();
+
+        assert_eq!(
+            port_string(&dvsec, 2).unwrap(),
+            "\t\tCXLPortSta:\tPMComplete+\n\
+             \t\tCXLPortCtl:\tUnmaskSBR+ UnmaskLinkDisable- AltMem+ AltBME- ViralEnable-\n\
+             \t\tAlternateBus:\t03-04\n\
+             \t\tAlternateMem:\ta000-a0f0\n"
+        );
+    }
+}
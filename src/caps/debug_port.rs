@@ -0,0 +1,57 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+/// The EHCI Debug Port capability, locating the debug port registers in a BAR.
+pub struct DebugPortCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    register: u16,
+}
+
+impl DebugPortCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<DebugPortCapability> {
+        let raw = access.read(offset as u64 + 2, 2)?;
+
+        Ok(DebugPortCapability {
+            _access: access,
+            offset,
+            register: BinaryParser::le16(&raw, 0..2)?,
+        })
+    }
+
+    /// BAR number as encoded in the capability, where 1 is the BAR at 0x10.
+    pub fn bar(&self) -> u8 {
+        (self.register >> 13) as u8
+    }
+
+    /// Offset of the debug port registers within the BAR.
+    pub fn bar_offset(&self) -> u16 {
+        self.register & 0x1fff
+    }
+}
+
+impl Capability for DebugPortCapability {
+    fn cap_string(&self, _verbosity: u8) -> Result<String> {
+        Ok(format!(
+            "Debug port: BAR={} offset={:0>4x}",
+            self.bar(),
+            self.bar_offset()
+        ))
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for DebugPortCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
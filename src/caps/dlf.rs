@@ -0,0 +1,116 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct DataLinkFeatureCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    capabilities: u32,
+    status: u32,
+}
+
+impl DataLinkFeatureCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<DataLinkFeatureCapability> {
+        let raw = access.read(offset as u64 + 4, 8)?;
+
+        Ok(DataLinkFeatureCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities: BinaryParser::le32(&raw, 0..4)?,
+            status: BinaryParser::le32(&raw, 4..8)?,
+        })
+    }
+
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    pub fn status(&self) -> u32 {
+        self.status
+    }
+
+    pub fn local_features(&self) -> u32 {
+        self.capabilities & 0x7f_ffff
+    }
+
+    pub fn remote_features(&self) -> Option<u32> {
+        match self.status & (1 << 31) != 0 {
+            true => Some(self.status & 0x7f_ffff),
+            false => None,
+        }
+    }
+}
+
+impl Capability for DataLinkFeatureCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Data Link Feature\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tDLFCap:\t{}\n",
+                Flags::new(
+                    self.capabilities,
+                    &[("ScaledFlowControl", 0), ("ExchangeEnable", 31)]
+                )
+            );
+            text += &format!(
+                "\t\tDLFSta:\t{}\n",
+                Flags::new(
+                    self.status,
+                    &[("RemoteScaledFlowControl", 0), ("RemoteValid", 31)]
+                )
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for DataLinkFeatureCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_features() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x200..0x204].copy_from_slice(&0x0001_0025u32.to_le_bytes());
+        // Local scaled flow control with exchange enabled, remote features valid without it
+        dump[0x204..0x208].copy_from_slice(&0x8000_0001u32.to_le_bytes());
+        dump[0x208..0x20c].copy_from_slice(&0x8000_0000u32.to_le_bytes());
+
+        let cap = DataLinkFeatureCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x200)
+            .unwrap();
+
+        assert_eq!(cap.local_features(), 0x1);
+        assert_eq!(cap.remote_features(), Some(0x0));
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Data Link Feature\n\
+             \t\tDLFCap:\tScaledFlowControl+ ExchangeEnable+\n\
+             \t\tDLFSta:\tRemoteScaledFlowControl- RemoteValid+"
+        );
+    }
+}
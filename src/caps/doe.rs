@@ -0,0 +1,126 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct DataObjectExchangeCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+
+    capabilities: u32,
+    control: u32,
+    status: u32,
+}
+
+impl DataObjectExchangeCapability {
+    pub const CAPABILITIES: u16 = 0x04;
+    pub const CONTROL: u16 = 0x08;
+    pub const STATUS: u16 = 0x0c;
+    pub const WRITE_MAILBOX: u16 = 0x10;
+    pub const READ_MAILBOX: u16 = 0x14;
+
+    pub const CONTROL_ABORT: u32 = 1 << 0;
+    pub const CONTROL_INTERRUPT_ENABLE: u32 = 1 << 1;
+    pub const CONTROL_GO: u32 = 1 << 31;
+
+    pub const STATUS_BUSY: u32 = 1 << 0;
+    pub const STATUS_INTERRUPT: u32 = 1 << 1;
+    pub const STATUS_ERROR: u32 = 1 << 2;
+    pub const STATUS_DATA_OBJECT_READY: u32 = 1 << 31;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<DataObjectExchangeCapability> {
+        let raw = access.read((offset + Self::CAPABILITIES).into(), 12)?;
+
+        Ok(DataObjectExchangeCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities: BinaryParser::le32(&raw, 0..4)?,
+            control: BinaryParser::le32(&raw, 4..8)?,
+            status: BinaryParser::le32(&raw, 8..12)?,
+        })
+    }
+
+    /// Returns every DOE instance of the function, a function may implement one per protocol.
+    pub fn find_all(access: &Rc<Box<dyn Access>>) -> Result<Vec<DataObjectExchangeCapability>> {
+        CapabilityFactory::lookup(Rc::clone(access))
+            .find_all_extended(0x002e)?
+            .into_iter()
+            .map(|offset| Self::new(Rc::clone(access), offset))
+            .collect()
+    }
+
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    pub fn control(&self) -> u32 {
+        self.control
+    }
+
+    pub fn status(&self) -> u32 {
+        self.status
+    }
+
+    pub fn interrupt_message_number(&self) -> Option<u16> {
+        match self.capabilities & 0x1 != 0 {
+            true => Some(((self.capabilities >> 1) & 0x7ff) as u16),
+            false => None,
+        }
+    }
+}
+
+impl Capability for DataObjectExchangeCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Data Object Exchange\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tDOECap: {}\n",
+                Flags::new(self.capabilities, &[("IntSup", 0)])
+            );
+            if let Some(number) = self.interrupt_message_number() {
+                text += &format!("\t\t\tIntMsgNum {}\n", number);
+            }
+            text += &format!(
+                "\t\tDOECtl: {}\n",
+                Flags::new(self.control, &[("IntEn", 1)])
+            );
+            text += &format!(
+                "\t\tDOESta: {}\n",
+                Flags::new(
+                    self.status,
+                    &[
+                        ("Busy", 0),
+                        ("IntSta", 1),
+                        ("Error", 2),
+                        ("ObjectReady", 31)
+                    ]
+                )
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for DataObjectExchangeCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
@@ -0,0 +1,345 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+const RP_PIO_ERRORS: &[(&str, u8)] = &[
+    ("CfgUR", 0),
+    ("CfgCA", 1),
+    ("CfgCTO", 2),
+    ("IOUR", 8),
+    ("IOCA", 9),
+    ("IOCTO", 10),
+    ("MemUR", 16),
+    ("MemCA", 17),
+    ("MemCTO", 18),
+];
+
+#[derive(Debug, PartialEq)]
+pub enum DpcTriggerReason {
+    UnmaskedUncorrectableError,
+    ErrNonFatal,
+    ErrFatal,
+    RpPioError,
+    SoftwareTrigger,
+    Reserved,
+}
+
+impl Display for DpcTriggerReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            DpcTriggerReason::UnmaskedUncorrectableError => "unmasked uncorrectable error",
+            DpcTriggerReason::ErrNonFatal => "ERR_NONFATAL received",
+            DpcTriggerReason::ErrFatal => "ERR_FATAL received",
+            DpcTriggerReason::RpPioError => "RP PIO error",
+            DpcTriggerReason::SoftwareTrigger => "software trigger",
+            DpcTriggerReason::Reserved => "reserved",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+pub struct DownstreamPortContainmentCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    raw: Vec<u8>,
+}
+
+impl DownstreamPortContainmentCapability {
+    const LENGTH: usize = 0x0c;
+    const RP_EXTENSIONS_LENGTH: usize = 0x44;
+
+    pub fn new(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+    ) -> Result<DownstreamPortContainmentCapability> {
+        let mut raw = access.read(offset.into(), Self::LENGTH)?;
+        if BinaryParser::le16(&raw, 0x04..0x06)? & (1 << 5) != 0 {
+            raw = access.read(offset.into(), Self::RP_EXTENSIONS_LENGTH)?;
+        }
+
+        Ok(DownstreamPortContainmentCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            raw,
+        })
+    }
+
+    pub fn capabilities(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x04..0x06)
+    }
+
+    pub fn control(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x06..0x08)
+    }
+
+    pub fn status(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x08..0x0a)
+    }
+
+    pub fn error_source_id(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x0a..0x0c)
+    }
+
+    pub fn rp_extensions(&self) -> Result<bool> {
+        Ok(self.capabilities()? & (1 << 5) != 0)
+    }
+
+    pub fn rp_pio_log_size(&self) -> Result<u8> {
+        let capabilities = self.capabilities()?;
+        Ok((((capabilities >> 8) & 0xf) | ((capabilities >> 9) & 0x10)) as u8)
+    }
+
+    pub fn triggered(&self) -> Result<bool> {
+        Ok(self.status()? & 0x1 != 0)
+    }
+
+    pub fn trigger_reason(&self) -> Result<DpcTriggerReason> {
+        let status = self.status()?;
+
+        Ok(match ((status >> 1) & 0x3, (status >> 5) & 0x3) {
+            (0, _) => DpcTriggerReason::UnmaskedUncorrectableError,
+            (1, _) => DpcTriggerReason::ErrNonFatal,
+            (2, _) => DpcTriggerReason::ErrFatal,
+            (_, 0) => DpcTriggerReason::RpPioError,
+            (_, 1) => DpcTriggerReason::SoftwareTrigger,
+            _ => DpcTriggerReason::Reserved,
+        })
+    }
+
+    pub fn rp_busy(&self) -> Result<bool> {
+        Ok(self.status()? & (1 << 4) != 0)
+    }
+
+    pub fn rp_pio_status(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x0c..0x10)
+    }
+
+    pub fn rp_pio_mask(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x10..0x14)
+    }
+
+    pub fn rp_pio_severity(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x14..0x18)
+    }
+
+    pub fn rp_pio_syserror(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x18..0x1c)
+    }
+
+    pub fn rp_pio_exception(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x1c..0x20)
+    }
+
+    fn dwords(&self, offset: usize, count: usize) -> Result<Vec<u32>> {
+        (0..count)
+            .map(|i| {
+                let start = offset + i * 4;
+                BinaryParser::le32(&self.raw, start..start + 4)
+            })
+            .collect()
+    }
+
+    pub fn rp_pio_header_log(&self) -> Result<Vec<u32>> {
+        match self.rp_pio_log_size()? {
+            0..=3 => Ok(vec![]),
+            _ => self.dwords(0x20, 4),
+        }
+    }
+
+    pub fn rp_pio_impspec_log(&self) -> Result<Option<u32>> {
+        match self.rp_pio_log_size()? {
+            0..=4 => Ok(None),
+            _ => Ok(Some(BinaryParser::le32(&self.raw, 0x30..0x34)?)),
+        }
+    }
+
+    pub fn rp_pio_tlp_prefix_log(&self) -> Result<Vec<u32>> {
+        let size = (self.rp_pio_log_size()? as usize).saturating_sub(5).min(4);
+        self.dwords(0x34, size)
+    }
+
+    fn log_string(log: &[u32]) -> String {
+        log.iter()
+            .map(|dword| format!("{:0>8x}", dword))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn rp_pio_string(&self) -> Result<String> {
+        let mut text = format!(
+            "\t\tRpPioSta:\t{}\n",
+            Flags::new(self.rp_pio_status()?, RP_PIO_ERRORS)
+        );
+        text += &format!(
+            "\t\tRpPioMsk:\t{}\n",
+            Flags::new(self.rp_pio_mask()?, RP_PIO_ERRORS)
+        );
+        text += &format!(
+            "\t\tRpPioSvrt:\t{}\n",
+            Flags::new(self.rp_pio_severity()?, RP_PIO_ERRORS)
+        );
+        text += &format!(
+            "\t\tRpPioSysErr:\t{}\n",
+            Flags::new(self.rp_pio_syserror()?, RP_PIO_ERRORS)
+        );
+        text += &format!(
+            "\t\tRpPioExc:\t{}\n",
+            Flags::new(self.rp_pio_exception()?, RP_PIO_ERRORS)
+        );
+
+        let header_log = self.rp_pio_header_log()?;
+        if !header_log.is_empty() {
+            text += &format!("\t\tRpPioHeaderLog:\t{}\n", Self::log_string(&header_log));
+        }
+        if let Some(log) = self.rp_pio_impspec_log()? {
+            text += &format!("\t\tRpPioImpSpecLog:\t{:0>8x}\n", log);
+        }
+        let prefix_log = self.rp_pio_tlp_prefix_log()?;
+        if !prefix_log.is_empty() {
+            text += &format!(
+                "\t\tRpPioTLPPrefixLog:\t{}\n",
+                Self::log_string(&prefix_log)
+            );
+        }
+
+        Ok(text)
+    }
+}
+
+impl Capability for DownstreamPortContainmentCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Downstream Port Containment\n".to_string();
+
+        if verbosity >= 2 {
+            let cap = self.capabilities()?;
+            text += &format!(
+                "\t\tDpcCap:\tINT Msg #{}, {} RP PIO Log {}, {}\n",
+                cap & 0x1f,
+                Flags::new(
+                    cap.into(),
+                    &[("RPExt", 5), ("PoisonedTLP", 6), ("SwTrigger", 7)]
+                ),
+                self.rp_pio_log_size()?,
+                Flags::new(cap.into(), &[("DL_ActiveErr", 12)])
+            );
+
+            let ctl = self.control()?;
+            text += &format!(
+                "\t\tDpcCtl:\tTrigger:{:x} {}\n",
+                ctl & 0x3,
+                Flags::new(
+                    ctl.into(),
+                    &[
+                        ("Cmpl", 2),
+                        ("INT", 3),
+                        ("ErrCor", 4),
+                        ("PoisonedTLP", 5),
+                        ("SwTrigger", 6),
+                        ("DL_ActiveErr", 7)
+                    ]
+                )
+            );
+
+            let sta = self.status()?;
+            text += &format!(
+                "\t\tDpcSta:\t{} Reason:{:0>2x} {} TriggerExt:{:0>2x} RP PIO ErrPtr:{:0>2x}\n",
+                Flags::new(sta.into(), &[("Trigger", 0)]),
+                (sta >> 1) & 0x3,
+                Flags::new(sta.into(), &[("INT", 3), ("RPBusy", 4)]),
+                (sta >> 5) & 0x3,
+                (sta >> 8) & 0x1f
+            );
+            if self.triggered()? {
+                text += &format!("\t\t\tTriggered by {}\n", self.trigger_reason()?);
+            }
+
+            text += &format!("\t\tSource:\t{:0>4x}\n", self.error_source_id()?);
+
+            if self.rp_extensions()? {
+                text += &self.rp_pio_string()?;
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for DownstreamPortContainmentCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_rp_pio_trigger() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x300..0x304].copy_from_slice(&0x0001_001du32.to_le_bytes());
+        // RP extensions with a 6 DWORD RP PIO log
+        dump[0x304..0x306].copy_from_slice(&0x06e0u16.to_le_bytes());
+        // Triggered by an RP PIO error, first error MemCTO
+        dump[0x308..0x30a].copy_from_slice(&0x1207u16.to_le_bytes());
+        dump[0x30a..0x30c].copy_from_slice(&0x0300u16.to_le_bytes());
+        dump[0x30c..0x310].copy_from_slice(&0x0004_0000u32.to_le_bytes());
+        dump[0x320..0x324].copy_from_slice(&0x4000_0001u32.to_le_bytes());
+        dump[0x334..0x338].copy_from_slice(&0x9100_0000u32.to_le_bytes());
+
+        let cap = DownstreamPortContainmentCapability::new(
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            0x300,
+        )
+        .unwrap();
+
+        assert!(cap.triggered().unwrap());
+        assert_eq!(cap.trigger_reason().unwrap(), DpcTriggerReason::RpPioError);
+        assert_eq!(cap.rp_pio_header_log().unwrap(), [0x4000_0001, 0, 0, 0]);
+        assert_eq!(cap.rp_pio_impspec_log().unwrap(), Some(0));
+        assert_eq!(cap.rp_pio_tlp_prefix_log().unwrap(), [0x9100_0000]);
+
+        let text = cap.cap_string(2).unwrap();
+        assert!(text.contains(
+            "\t\tDpcSta:\tTrigger+ Reason:03 INT- RPBusy- TriggerExt:00 RP PIO ErrPtr:12\n\t\t\tTriggered by RP PIO error\n"
+        ));
+        assert!(text.contains("\t\tSource:\t0300\n"));
+        assert!(text.contains(
+            "\t\tRpPioSta:\tCfgUR- CfgCA- CfgCTO- IOUR- IOCA- IOCTO- MemUR- MemCA- MemCTO+\n"
+        ));
+    }
+
+    #[test]
+    fn test_rp_pio_log_size() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x300..0x304].copy_from_slice(&0x0001_001du32.to_le_bytes());
+        // RP PIO Log Size 0x1a, with bit 4 of the size in bit 13
+        dump[0x304..0x306].copy_from_slice(&0x2ae0u16.to_le_bytes());
+
+        let cap = DownstreamPortContainmentCapability::new(
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            0x300,
+        )
+        .unwrap();
+
+        assert_eq!(cap.rp_pio_log_size().unwrap(), 0x1a);
+        assert_eq!(cap.rp_pio_tlp_prefix_log().unwrap().len(), 4);
+    }
+}
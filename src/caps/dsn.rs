@@ -0,0 +1,93 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+pub struct DeviceSerialNumberCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    serial_number: u64,
+}
+
+impl DeviceSerialNumberCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<DeviceSerialNumberCapability> {
+        let raw = access.read(offset as u64 + 4, 8)?;
+        let low = BinaryParser::le32(&raw, 0..4)?;
+        let high = BinaryParser::le32(&raw, 4..8)?;
+
+        Ok(DeviceSerialNumberCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            serial_number: (high as u64) << 32 | low as u64,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<DeviceSerialNumberCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_extended(0x0003)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn serial_number(&self) -> u64 {
+        self.serial_number
+    }
+}
+
+impl Capability for DeviceSerialNumberCapability {
+    fn cap_string(&self, _verbosity: u8) -> Result<String> {
+        let bytes: Vec<_> = self
+            .serial_number
+            .to_be_bytes()
+            .iter()
+            .map(|b| format!("{:0>2x}", b))
+            .collect();
+
+        Ok(format!("Device Serial Number {}", bytes.join("-")))
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for DeviceSerialNumberCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_serial_number() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x140..0x14c].copy_from_slice(&[
+            0x03, 0x00, 0x01, 0x00, 0x44, 0x33, 0x22, 0x11, 0x00, 0x65, 0x4b, 0x3c,
+        ]);
+
+        let cap =
+            DeviceSerialNumberCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x140)
+                .unwrap();
+
+        assert_eq!(cap.serial_number(), 0x3c4b_6500_1122_3344);
+        assert_eq!(
+            cap.to_string(),
+            "Device Serial Number 3c-4b-65-00-11-22-33-44"
+        );
+    }
+}
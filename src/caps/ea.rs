@@ -0,0 +1,331 @@
+use crate::access::Access;
+use crate::bar::{IoBAR, MemBAR, BAR};
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::header::Header;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::{Capability, Flag};
+
+/// One Enhanced Allocation entry, describing a resource with a fixed address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EaEntry {
+    pub entry_size: u8,
+    pub bar_equivalent_indicator: u8,
+    pub primary_properties: u8,
+    pub secondary_properties: u8,
+    pub writable: bool,
+    pub enable: bool,
+    pub base: u64,
+    pub max_offset: u64,
+    pub is_64bit: bool,
+}
+
+impl EaEntry {
+    fn parse(raw: &[u8]) -> Result<EaEntry> {
+        let header = BinaryParser::le32(raw, 0..4)?;
+        let entry_size = (header & 0x7) as u8;
+
+        let mut dwords = vec![];
+        for index in 1..=entry_size as usize {
+            dwords.push(BinaryParser::le32(raw, index * 4..index * 4 + 4)?);
+        }
+
+        let base = dwords.first().copied().unwrap_or(0);
+        let max_offset = dwords.get(1).copied().unwrap_or(0);
+        let mut upper = dwords.iter().skip(2);
+        let base_hi = match base & 0x2 != 0 {
+            true => upper.next().copied().unwrap_or(0),
+            false => 0,
+        };
+        let max_offset_hi = match max_offset & 0x2 != 0 {
+            true => upper.next().copied().unwrap_or(0),
+            false => 0,
+        };
+
+        Ok(EaEntry {
+            entry_size,
+            bar_equivalent_indicator: ((header >> 4) & 0xf) as u8,
+            primary_properties: (header >> 8) as u8,
+            secondary_properties: (header >> 16) as u8,
+            writable: header & (1 << 30) != 0,
+            enable: header & (1 << 31) != 0,
+            base: (base_hi as u64) << 32 | (base & !0x3) as u64,
+            max_offset: (max_offset_hi as u64) << 32 | (max_offset | 0x3) as u64,
+            is_64bit: base & 0x2 != 0,
+        })
+    }
+
+    /// Length in bytes of the entry, including its header.
+    pub fn length(&self) -> usize {
+        (self.entry_size as usize + 1) * 4
+    }
+
+    pub fn bar_equivalent_indicator_string(&self) -> String {
+        match self.bar_equivalent_indicator {
+            bei @ 0..=5 => format!("BAR {}", bei),
+            6 => "resource behind function or for use by function".to_string(),
+            7 => "not indicated".to_string(),
+            8 => "expansion ROM".to_string(),
+            bei @ 9..=14 => format!("VF-BAR {}", bei - 9),
+            _ => "reserved".to_string(),
+        }
+    }
+
+    pub fn properties_string(properties: u8) -> String {
+        match properties {
+            0x00 => "memory space, non-prefetchable".to_string(),
+            0x01 => "memory space, prefetchable".to_string(),
+            0x02 => "I/O space".to_string(),
+            0x03 => "VF memory space, prefetchable".to_string(),
+            0x04 => "VF memory space, non-prefetchable".to_string(),
+            0x05 => "allocation behind bridge, non-prefetchable memory".to_string(),
+            0x06 => "allocation behind bridge, prefetchable memory".to_string(),
+            0x07 => "allocation behind bridge, I/O space".to_string(),
+            0xfd => "memory space resource unavailable for use".to_string(),
+            0xfe => "I/O space resource unavailable for use".to_string(),
+            0xff => "entry unavailable for use".to_string(),
+            properties => format!("[{:0>2x}]", properties),
+        }
+    }
+
+    /// The entry as a function resource, if it is enabled and stands in for one of the BARs.
+    pub fn resource(&self) -> Option<BAR> {
+        if !self.enable || self.bar_equivalent_indicator > 5 {
+            return None;
+        }
+
+        match self.primary_properties {
+            0x00 | 0x01 => {
+                let prefechable = self.primary_properties == 0x01;
+                match self.is_64bit {
+                    true => Some(BAR::MemBAR64(MemBAR {
+                        address: self.base,
+                        prefechable,
+                    })),
+                    false => Some(BAR::MemBAR32(MemBAR {
+                        address: self.base as u32,
+                        prefechable,
+                    })),
+                }
+            }
+            0x02 => Some(BAR::IoBAR(IoBAR {
+                address: self.base as u32,
+            })),
+            _ => None,
+        }
+    }
+}
+
+/// The Enhanced Allocation capability, listing resources at fixed addresses in place of BARs.
+pub struct EnhancedAllocationCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    bus_numbers: Option<(u8, u8)>,
+    entries: Vec<EaEntry>,
+}
+
+impl EnhancedAllocationCapability {
+    const END: u64 = 0x100;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<EnhancedAllocationCapability> {
+        let bridge = matches!(Header::new(&access.read(0, 0x40)?)?, Header::Type1(_));
+        let count = BinaryParser::le8(&access.read(offset as u64 + 2, 1)?, 0..1)? & 0x3f;
+
+        let bus_numbers = match bridge {
+            true => {
+                let raw = access.read(offset as u64 + 4, 2)?;
+                Some((
+                    BinaryParser::le8(&raw, 0..1)?,
+                    BinaryParser::le8(&raw, 1..2)?,
+                ))
+            }
+            false => None,
+        };
+
+        let mut next = offset as u64 + if bridge { 8 } else { 4 };
+        let mut entries = vec![];
+        for _ in 0..count {
+            // Entries past the end of the traditional capability space are not decoded
+            if next + 4 > Self::END {
+                break;
+            }
+            let header = BinaryParser::le32(&access.read(next, 4)?, 0..4)?;
+            let length = ((header & 0x7) as usize + 1) * 4;
+            if next + length as u64 > Self::END {
+                break;
+            }
+            let entry = EaEntry::parse(&access.read(next, length)?)?;
+
+            next += entry.length() as u64;
+            entries.push(entry);
+        }
+
+        Ok(EnhancedAllocationCapability {
+            _access: access,
+            offset,
+            bus_numbers,
+            entries,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<EnhancedAllocationCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_trad(0x14)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn entries(&self) -> &[EaEntry] {
+        &self.entries
+    }
+
+    /// Fixed secondary and subordinate bus numbers of a bridge.
+    pub fn bus_numbers(&self) -> Option<(u8, u8)> {
+        self.bus_numbers
+    }
+
+    /// Enabled entries that stand in for BARs, as they would be listed for a BAR.
+    pub fn resources(&self) -> Vec<BAR> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.resource())
+            .collect()
+    }
+}
+
+impl Capability for EnhancedAllocationCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!(
+            "Enhanced Allocation (EA): NumEntries={}",
+            self.entries.len()
+        );
+        if let Some((secondary, subordinate)) = self.bus_numbers {
+            text = format!(
+                "{}, secondary={}, subordinate={}",
+                text, secondary, subordinate
+            );
+        }
+
+        if verbosity >= 2 {
+            for (index, entry) in self.entries.iter().enumerate() {
+                text = format!(
+                    "{}\n\t\tEntry {}: {} {} EntrySize={}",
+                    text,
+                    index,
+                    Flag::new("Enable", entry.enable),
+                    Flag::new("Writable", entry.writable),
+                    entry.entry_size
+                );
+                text = format!(
+                    "{}\n\t\t\t BAR Equivalent Indicator: {}",
+                    text,
+                    entry.bar_equivalent_indicator_string()
+                );
+                text = format!(
+                    "{}\n\t\t\t PrimaryProperties: {}",
+                    text,
+                    EaEntry::properties_string(entry.primary_properties)
+                );
+                text = format!(
+                    "{}\n\t\t\t SecondaryProperties: {}",
+                    text,
+                    EaEntry::properties_string(entry.secondary_properties)
+                );
+                text = format!(
+                    "{}\n\t\t\t Base: {:x}{:0>8x}",
+                    text,
+                    entry.base >> 32,
+                    entry.base as u32
+                );
+                text = format!(
+                    "{}\n\t\t\t MaxOffset: {:x}{:0>8x}",
+                    text,
+                    entry.max_offset >> 32,
+                    entry.max_offset as u32
+                );
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for EnhancedAllocationCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_entries() {
+        let mut dump = vec![0; 0x100];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x80;
+        dump[0x80..0x84].copy_from_slice(&0x0002_0014u32.to_le_bytes());
+        // BAR 0, 64-bit prefetchable memory
+        dump[0x84..0x88].copy_from_slice(&0x8000_0103u32.to_le_bytes());
+        dump[0x88..0x8c].copy_from_slice(&0xfe00_0002u32.to_le_bytes());
+        dump[0x8c..0x90].copy_from_slice(&0x000f_fffcu32.to_le_bytes());
+        dump[0x90..0x94].copy_from_slice(&0x0000_0001u32.to_le_bytes());
+        // Disabled I/O entry
+        dump[0x94..0x98].copy_from_slice(&0x4002_0222u32.to_le_bytes());
+        dump[0x98..0x9c].copy_from_slice(&0x0000_1000u32.to_le_bytes());
+        dump[0x9c..0xa0].copy_from_slice(&0x0000_00fcu32.to_le_bytes());
+
+        let cap =
+            EnhancedAllocationCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x80)
+                .unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Enhanced Allocation (EA): NumEntries=2\n\
+             \t\tEntry 0: Enable+ Writable- EntrySize=3\n\
+             \t\t\t BAR Equivalent Indicator: BAR 0\n\
+             \t\t\t PrimaryProperties: memory space, prefetchable\n\
+             \t\t\t SecondaryProperties: memory space, non-prefetchable\n\
+             \t\t\t Base: 1fe000000\n\
+             \t\t\t MaxOffset: 0000fffff\n\
+             \t\tEntry 1: Enable- Writable+ EntrySize=2\n\
+             \t\t\t BAR Equivalent Indicator: BAR 2\n\
+             \t\t\t PrimaryProperties: I/O space\n\
+             \t\t\t SecondaryProperties: I/O space\n\
+             \t\t\t Base: 000001000\n\
+             \t\t\t MaxOffset: 0000000ff"
+        );
+        assert_eq!(
+            cap.resources(),
+            vec![BAR::MemBAR64(MemBAR {
+                address: 0x1_fe00_0000,
+                prefechable: true
+            })]
+        );
+    }
+
+    #[test]
+    fn test_end_of_config_space() {
+        let mut dump = vec![0; 0x100];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0xf8;
+        dump[0xf8..0xfc].copy_from_slice(&0x0002_0014u32.to_le_bytes());
+        dump[0xfc..0x100].copy_from_slice(&0x8000_0002u32.to_le_bytes());
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(DumpAccess::new(&dump)));
+        let cap = EnhancedAllocationCapability::new(Rc::clone(&access), 0xf8).unwrap();
+        assert_eq!(cap.entries().len(), 0);
+
+        assert!(EnhancedAllocationCapability::new(access, 0xfe).is_err());
+    }
+}
@@ -102,6 +102,10 @@ pub trait CommonHeader {
         )
     }
 
+    fn is_multifunction(&self) -> Result<bool> {
+        Ok(self.header_type()? & !Self::HEADER_TYPE_LAYOUT_MASK != 0)
+    }
+
     fn capability_pointer(&self) -> Result<u8> {
         BinaryParser::le8(
             self.get_raw(),
@@ -157,6 +161,45 @@ pub trait CommonHeader {
         Ok(text)
     }
 
+    /// Returns the BAR decoded from the register at `index`, where 64-bit BARs occupy two
+    /// register indices as in a BAR Indicator Register (BIR).
+    fn bar(&self, index: u8) -> Result<Option<BAR>> {
+        Ok(BAR::at(self.bars()?, index))
+    }
+
+    /// Formats the subsystem of the function, as found in a Type 0 header or in the Subsystem
+    /// Vendor ID capability of a bridge.
+    fn subsystem_string_for(&self, subsystem_vendor: u16, subsystem_device: u16) -> Result<String> {
+        let device_id = self.device_id()?;
+        let vendor_id = self.vendor_id()?;
+
+        let device = Device::from_vid_pid(vendor_id, device_id);
+        if device.is_none() {
+            return Ok(format!(
+                "Subsystem: {} Device {:0>4x}",
+                self.vendor_name()?,
+                subsystem_device
+            ));
+        }
+
+        let device = device.unwrap();
+        if let Some(device) = device
+            .subsystems()
+            .find(|d| d.subdevice() == subsystem_device)
+        {
+            return Ok(format!(
+                "Subsystem: {} {}",
+                self.vendor_name()?,
+                device.name()
+            ));
+        }
+
+        Ok(format!(
+            "Subsystem: Vendor {:0>4x} Device {:0>4x}",
+            subsystem_vendor, subsystem_device
+        ))
+    }
+
     fn bars_string(&self) -> Result<Vec<String>> {
         let mut text = vec![];
 
@@ -186,6 +229,7 @@ pub trait CommonHeader {
 #[derive(Debug)]
 pub struct Type0Header {
     raw: Vec<u8>,
+    enhanced_allocation: Vec<BAR>,
 }
 
 impl CommonHeader for Type0Header {
@@ -213,6 +257,9 @@ impl CommonHeader for Type0Header {
             for bar in self.bars_string()? {
                 text = format!("{}\n\t{}", text, bar);
             }
+            for bar in &self.enhanced_allocation {
+                text = format!("{}\n\t{} [enhanced]", text, bar);
+            }
         }
 
         Ok(text.trim().to_string())
@@ -221,7 +268,10 @@ impl CommonHeader for Type0Header {
 
 impl Type0Header {
     pub fn new(b: &[u8]) -> Result<Self> {
-        Ok(Self { raw: b.to_vec() })
+        Ok(Self {
+            raw: b.to_vec(),
+            enhanced_allocation: vec![],
+        })
     }
 
     pub fn subsystem_vendor_id(&self) -> Result<u16> {
@@ -245,43 +295,23 @@ impl Type0Header {
     }
 
     fn subsytem_string(&self) -> Result<String> {
-        let subsystem_device = self.subsystem_id()?;
-        let subsystem_vendor = self.subsystem_vendor_id()?;
-
-        let device_id = self.device_id()?;
-        let vendor_id = self.vendor_id()?;
-
-        let device = Device::from_vid_pid(vendor_id, device_id);
-        if device.is_none() {
-            return Ok(format!(
-                "Subsystem: {} Device {:0>4x}",
-                self.vendor_name()?,
-                subsystem_device
-            ));
-        }
+        self.subsystem_string_for(self.subsystem_vendor_id()?, self.subsystem_id()?)
+    }
 
-        let device = device.unwrap();
-        if let Some(device) = device
-            .subsystems()
-            .find(|d| d.subdevice() == subsystem_device)
-        {
-            return Ok(format!(
-                "Subsystem: {} {}",
-                self.vendor_name()?,
-                device.name()
-            ));
-        }
+    /// Adds resources assigned through Enhanced Allocation to the BAR listing.
+    pub fn set_enhanced_allocation(&mut self, resources: Vec<BAR>) {
+        self.enhanced_allocation = resources;
+    }
 
-        Ok(format!(
-            "Subsystem: Vendor {:0>4x} Device {:0>4x}",
-            subsystem_vendor, subsystem_device
-        ))
+    pub fn enhanced_allocation(&self) -> &[BAR] {
+        &self.enhanced_allocation
     }
 }
 
 #[derive(Debug)]
 pub struct Type1Header {
     raw: Vec<u8>,
+    subsystem: Option<(u16, u16)>,
 }
 
 impl CommonHeader for Type1Header {
@@ -305,6 +335,9 @@ impl CommonHeader for Type1Header {
         let mut text = self.device_string()?;
 
         if verbosity >= 1 {
+            if let Some((vendor, device)) = self.subsystem {
+                text = format!("{}\n\t{}", text, self.subsystem_string_for(vendor, device)?);
+            }
             text = format!("{}\n\t{}", text, self.bus_string()?);
         }
 
@@ -314,7 +347,24 @@ impl CommonHeader for Type1Header {
 
 impl Type1Header {
     pub fn new(b: &[u8]) -> Result<Self> {
-        Ok(Self { raw: b.to_vec() })
+        Ok(Self {
+            raw: b.to_vec(),
+            subsystem: None,
+        })
+    }
+
+    /// Sets the subsystem vendor and device IDs, which bridges report through the Subsystem
+    /// Vendor ID capability.
+    pub fn set_subsystem(&mut self, vendor: u16, device: u16) {
+        self.subsystem = Some((vendor, device));
+    }
+
+    pub fn subsystem_vendor_id(&self) -> Option<u16> {
+        self.subsystem.map(|(vendor, _)| vendor)
+    }
+
+    pub fn subsystem_id(&self) -> Option<u16> {
+        self.subsystem.map(|(_, device)| device)
     }
 
     pub fn primary_bus_number(&self) -> Result<u8> {
@@ -0,0 +1,38 @@
+use crate::access::Access;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+/// The PCI Standard Hot-Plug Controller capability, which only marks the function as hot-plug
+/// capable.
+pub struct HotPlugCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+}
+
+impl HotPlugCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<HotPlugCapability> {
+        Ok(HotPlugCapability {
+            _access: access,
+            offset,
+        })
+    }
+}
+
+impl Capability for HotPlugCapability {
+    fn cap_string(&self, _verbosity: u8) -> Result<String> {
+        Ok("Hot-plug capable".to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for HotPlugCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
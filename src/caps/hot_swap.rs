@@ -0,0 +1,63 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+/// The CompactPCI Hot Swap capability, whose control/status register lspci does not decode.
+pub struct CompactPciHotSwapCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    control: u8,
+}
+
+impl CompactPciHotSwapCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<CompactPciHotSwapCapability> {
+        let raw = access.read(offset as u64 + 2, 1)?;
+
+        Ok(CompactPciHotSwapCapability {
+            _access: access,
+            offset,
+            control: BinaryParser::le8(&raw, 0..1)?,
+        })
+    }
+
+    pub fn control(&self) -> u8 {
+        self.control
+    }
+
+    pub fn enum_interrupt_masked(&self) -> bool {
+        self.control & (1 << 1) != 0
+    }
+
+    pub fn led_on(&self) -> bool {
+        self.control & (1 << 3) != 0
+    }
+
+    pub fn extracting(&self) -> bool {
+        self.control & (1 << 6) != 0
+    }
+
+    pub fn inserted(&self) -> bool {
+        self.control & (1 << 7) != 0
+    }
+}
+
+impl Capability for CompactPciHotSwapCapability {
+    fn cap_string(&self, _verbosity: u8) -> Result<String> {
+        Ok("CompactPCI hot-swap <?>".to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for CompactPciHotSwapCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
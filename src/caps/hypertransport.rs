@@ -0,0 +1,469 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flag;
+use super::Flags;
+
+const LINK_WIDTH: [&str; 8] = [
+    "8bit", "16bit", "[2]", "32bit", "2bit", "4bit", "[6]", "N/C",
+];
+
+const LINK_FREQUENCY: [&str; 16] = [
+    "200MHz", "300MHz", "400MHz", "500MHz", "600MHz", "800MHz", "1.0GHz", "1.2GHz", "1.4GHz",
+    "1.6GHz", "[a]", "[b]", "[c]", "[d]", "[e]", "Vend",
+];
+
+const ERROR_HANDLING: &[(&str, u8)] = &[
+    ("PFlE", 0),
+    ("OFlE", 1),
+    ("PFE", 2),
+    ("OFE", 3),
+    ("EOCFE", 4),
+    ("RFE", 5),
+    ("CRCFE", 6),
+    ("SERRFE", 7),
+    ("CF", 8),
+    ("RE", 9),
+    ("PNFE", 10),
+    ("ONFE", 11),
+    ("EOCNFE", 12),
+    ("RNFE", 13),
+    ("CRCNFE", 14),
+    ("SERRNFE", 15),
+];
+
+// Revision 1.03 and later use the extended register layouts.
+const EXTENDED_REVISION: u8 = 0x22;
+
+/// The kind of HyperTransport capability, selected by the upper bits of the command register.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HyperTransportType {
+    Primary,
+    Secondary,
+    Switch,
+    InterruptDiscovery,
+    RevisionId,
+    UnitIdClumping,
+    ExtendedConfigurationSpace,
+    AddressMapping,
+    MsiMapping,
+    DirectRoute,
+    VcSet,
+    RetryMode,
+    X86,
+    Unknown(u8),
+}
+
+impl HyperTransportType {
+    pub fn new(command: u16) -> HyperTransportType {
+        match command >> 13 {
+            0 => return HyperTransportType::Primary,
+            1 => return HyperTransportType::Secondary,
+            _ => (),
+        }
+
+        match command >> 11 {
+            0x08 => HyperTransportType::Switch,
+            0x10 => HyperTransportType::InterruptDiscovery,
+            0x11 => HyperTransportType::RevisionId,
+            0x12 => HyperTransportType::UnitIdClumping,
+            0x13 => HyperTransportType::ExtendedConfigurationSpace,
+            0x14 => HyperTransportType::AddressMapping,
+            0x15 => HyperTransportType::MsiMapping,
+            0x16 => HyperTransportType::DirectRoute,
+            0x17 => HyperTransportType::VcSet,
+            0x18 => HyperTransportType::RetryMode,
+            0x19 => HyperTransportType::X86,
+            kind => HyperTransportType::Unknown(kind as u8),
+        }
+    }
+}
+
+pub struct HyperTransportCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    command: u16,
+    raw: Vec<u8>,
+}
+
+impl HyperTransportCapability {
+    const LENGTH: usize = 0x1c;
+    const PRIMARY_LENGTH: usize = 0x1c;
+    const SECONDARY_LENGTH: usize = 0x18;
+    const MSI_MAPPING_LENGTH: usize = 0x0c;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<HyperTransportCapability> {
+        // The capability may sit at the very end of the 256 byte PCI configuration space
+        let raw = access.read(offset.into(), Self::LENGTH.min(0x100 - offset as usize))?;
+
+        Ok(HyperTransportCapability {
+            _access: access,
+            offset,
+            command: BinaryParser::le16(&raw, 0x02..0x04)?,
+            raw,
+        })
+    }
+
+    pub fn command(&self) -> u16 {
+        self.command
+    }
+
+    pub fn kind(&self) -> HyperTransportType {
+        HyperTransportType::new(self.command)
+    }
+
+    /// Whether the registers the type needs fit before the end of the PCI configuration space.
+    fn is_complete(&self, length: usize) -> bool {
+        self.raw.len() >= length
+    }
+
+    fn byte(&self, offset: usize) -> Result<u32> {
+        Ok(BinaryParser::le8(&self.raw, offset..offset + 1)? as u32)
+    }
+
+    fn word(&self, offset: usize) -> Result<u32> {
+        Ok(BinaryParser::le16(&self.raw, offset..offset + 2)? as u32)
+    }
+
+    fn link_control_string(name: &str, control: u32, extended: bool) -> String {
+        let mut text = format!(
+            "\t\t{}: {} <{} {} <CRCErr={:x}",
+            name,
+            Flags::new(control, &[("CFlE", 1), ("CST", 2), ("CFE", 3)]),
+            Flag::new("LkFail", control & (1 << 4) != 0),
+            Flags::new(control, &[("Init", 5), ("EOC", 6), ("TXO", 7)]),
+            (control >> 8) & 0xf
+        );
+
+        if extended {
+            text += &format!(
+                " {}",
+                Flags::new(
+                    control,
+                    &[("IsocEn", 12), ("LSEn", 13), ("ExtCTL", 14), ("64b", 15)]
+                )
+            );
+        }
+
+        text + "\n"
+    }
+
+    fn link_config_string(name: &str, config: u32, extended: bool) -> String {
+        let width = |shift: u32| LINK_WIDTH[((config >> shift) & 0x7) as usize];
+
+        match extended {
+            true => format!(
+                "\t\t{}: MLWI={} {} MLWO={} {} LWI={} {} LWO={} {}\n",
+                name,
+                width(0),
+                Flag::new("DwFcIn", config & (1 << 3) != 0),
+                width(4),
+                Flag::new("DwFcOut", config & (1 << 7) != 0),
+                width(8),
+                Flag::new("DwFcInEn", config & (1 << 11) != 0),
+                width(12),
+                Flag::new("DwFcOutEn", config & (1 << 15) != 0)
+            ),
+            false => format!(
+                "\t\t{}: MLWI={} MLWO={} LWI={} LWO={}\n",
+                name,
+                width(0),
+                width(4),
+                width(8),
+                width(12)
+            ),
+        }
+    }
+
+    fn link_frequency_string(index: &str, frequency: u32, capability: u32) -> String {
+        let mut text = format!(
+            "\t\tLink Frequency{}: {}\n",
+            index,
+            LINK_FREQUENCY[(frequency & 0xf) as usize]
+        );
+        text += &format!(
+            "\t\tLink Error{}: <{} <{} <{} {}\n",
+            index,
+            Flag::new("Prot", frequency & (1 << 4) != 0),
+            Flag::new("Ovfl", frequency & (1 << 5) != 0),
+            Flag::new("EOC", frequency & (1 << 6) != 0),
+            Flag::new("CTLTm", frequency & (1 << 7) != 0)
+        );
+
+        let rates: Vec<_> = LINK_FREQUENCY[..10]
+            .iter()
+            .enumerate()
+            .map(|(bit, name)| Flag::new(name, capability & (1 << bit) != 0).to_string())
+            .collect();
+        text += &format!(
+            "\t\tLink Frequency Capability{}: {} {}\n",
+            index,
+            rates.join(" "),
+            Flag::new("Vend", capability & (1 << 15) != 0)
+        );
+
+        text
+    }
+
+    fn revision_string(revision: u32) -> String {
+        format!(
+            "\t\tRevision ID: {}.{:0>2}\n",
+            revision >> 5,
+            revision & 0x1f
+        )
+    }
+
+    fn primary_string(&self) -> Result<String> {
+        if !self.is_complete(Self::PRIMARY_LENGTH) {
+            return Ok(String::new());
+        }
+
+        let command = self.command as u32;
+        let revision = self.byte(0x0c)?;
+        let extended = revision >= EXTENDED_REVISION as u32;
+
+        let mut text = String::new();
+        if revision > 0x11 && !extended {
+            text += "\t\t!!! Possibly incomplete decoding\n";
+        }
+
+        text += &format!(
+            "\t\tCommand: BaseUnitID={} UnitCnt={} {}",
+            command & 0x1f,
+            (command >> 5) & 0x1f,
+            Flags::new(command, &[("MastHost", 10), ("DefDir", 11)])
+        );
+        if extended {
+            text += &format!(" {}", Flag::new("DUL", command & (1 << 12) != 0));
+        }
+        text += "\n";
+
+        text += &Self::link_control_string("Link Control 0", self.word(0x04)?, extended);
+        text += &Self::link_config_string("Link Config 0", self.word(0x06)?, extended);
+        text += &Self::link_control_string("Link Control 1", self.word(0x08)?, extended);
+        text += &Self::link_config_string("Link Config 1", self.word(0x0a)?, extended);
+        text += &Self::revision_string(revision);
+
+        if !extended {
+            return Ok(text);
+        }
+
+        text += &Self::link_frequency_string(" 0", self.byte(0x0d)?, self.word(0x0e)?);
+        text += &format!(
+            "\t\tFeature Capability: {}\n",
+            Flags::new(
+                self.byte(0x10)?,
+                &[
+                    ("IsocFC", 0),
+                    ("LDTSTOP", 1),
+                    ("CRCTM", 2),
+                    ("ECTLT", 3),
+                    ("64bA", 4),
+                    ("UIDRD", 5)
+                ]
+            )
+        );
+        text += &Self::link_frequency_string(" 1", self.byte(0x11)?, self.word(0x12)?);
+        text += &format!(
+            "\t\tError Handling: {}\n",
+            Flags::new(self.word(0x16)?, ERROR_HANDLING)
+        );
+        text += &format!(
+            "\t\tPrefetchable memory behind bridge Upper: {:0>2x}-{:0>2x}\n",
+            self.byte(0x18)?,
+            self.byte(0x19)?
+        );
+
+        Ok(text)
+    }
+
+    fn secondary_string(&self) -> Result<String> {
+        if !self.is_complete(Self::SECONDARY_LENGTH) {
+            return Ok(String::new());
+        }
+
+        let command = self.command as u32;
+        let revision = self.byte(0x08)?;
+        let extended = revision >= EXTENDED_REVISION as u32;
+
+        let mut text = String::new();
+        if revision > 0x11 && !extended {
+            text += "\t\t!!! Possibly incomplete decoding\n";
+        }
+
+        text += &format!(
+            "\t\tCommand: {} DevNum={} {} <{}",
+            Flags::new(command, &[("WarmRst", 0), ("DblEnd", 1)]),
+            (command >> 2) & 0x1f,
+            Flags::new(command, &[("ChainSide", 7), ("HostHide", 8), ("Slave", 10)]),
+            Flag::new("EOCErr", command & (1 << 11) != 0)
+        );
+        if extended {
+            text += &format!(" {}", Flag::new("DUL", command & (1 << 12) != 0));
+        }
+        text += "\n";
+
+        text += &Self::link_control_string("Link Control", self.word(0x04)?, extended);
+        text += &Self::link_config_string("Link Config", self.word(0x06)?, extended);
+        text += &Self::revision_string(revision);
+
+        if !extended {
+            return Ok(text);
+        }
+
+        text += &Self::link_frequency_string("", self.byte(0x09)?, self.word(0x0a)?);
+
+        let features = self.word(0x0c)?;
+        text += &format!(
+            "\t\tFeature Capability: {}\n",
+            Flags::new(
+                features,
+                &[
+                    ("IsocFC", 0),
+                    ("LDTSTOP", 1),
+                    ("CRCTM", 2),
+                    ("ECTLT", 3),
+                    ("64bA", 4),
+                    ("UIDRD", 5),
+                    ("ExtRS", 8),
+                    ("UCnfE", 9)
+                ]
+            )
+        );
+
+        if features & (1 << 8) != 0 {
+            text += &format!(
+                "\t\tError Handling: {}\n",
+                Flags::new(self.word(0x10)?, ERROR_HANDLING)
+            );
+            text += &format!(
+                "\t\tPrefetchable memory behind bridge Upper: {:0>2x}-{:0>2x}\n",
+                self.byte(0x12)?,
+                self.byte(0x13)?
+            );
+        }
+
+        Ok(text)
+    }
+
+    fn msi_mapping_string(&self, verbosity: u8) -> Result<String> {
+        let command = self.command as u32;
+        let mut text = format!(
+            "HyperTransport: MSI Mapping {}\n",
+            Flags::new(command, &[("Enable", 0), ("Fixed", 1)])
+        );
+
+        if verbosity >= 2 && command & (1 << 1) == 0 && self.is_complete(Self::MSI_MAPPING_LENGTH) {
+            let low = BinaryParser::le32(&self.raw, 0x04..0x08)?;
+            let high = BinaryParser::le32(&self.raw, 0x08..0x0c)?;
+            text += &format!(
+                "\t\tMapping Address Base: {:0>16x}\n",
+                (high as u64) << 32 | (low & !0xfffff) as u64
+            );
+        }
+
+        Ok(text)
+    }
+}
+
+impl Capability for HyperTransportCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let text = match self.kind() {
+            HyperTransportType::Primary => {
+                let mut text = "HyperTransport: Slave or Primary Interface\n".to_string();
+                if verbosity >= 2 {
+                    text += &self.primary_string()?;
+                }
+                text
+            }
+            HyperTransportType::Secondary => {
+                let mut text = "HyperTransport: Host or Secondary Interface\n".to_string();
+                if verbosity >= 2 {
+                    text += &self.secondary_string()?;
+                }
+                text
+            }
+            HyperTransportType::MsiMapping => self.msi_mapping_string(verbosity)?,
+            HyperTransportType::Switch => "HyperTransport: Switch".to_string(),
+            HyperTransportType::InterruptDiscovery => {
+                "HyperTransport: Interrupt Discovery and Configuration".to_string()
+            }
+            HyperTransportType::RevisionId => format!(
+                "HyperTransport: Revision ID: {:x}.{:0>2x}",
+                (self.command >> 5) & 0x7,
+                self.command & 0x1f
+            ),
+            HyperTransportType::UnitIdClumping => "HyperTransport: UnitID Clumping".to_string(),
+            HyperTransportType::ExtendedConfigurationSpace => {
+                "HyperTransport: Extended Configuration Space Access".to_string()
+            }
+            HyperTransportType::AddressMapping => "HyperTransport: Address Mapping".to_string(),
+            HyperTransportType::DirectRoute => "HyperTransport: DirectRoute".to_string(),
+            HyperTransportType::VcSet => "HyperTransport: VCSet".to_string(),
+            HyperTransportType::RetryMode => "HyperTransport: Retry Mode".to_string(),
+            HyperTransportType::X86 => "HyperTransport: X86 (reserved)".to_string(),
+            HyperTransportType::Unknown(kind) => format!("HyperTransport: #{:0>2x}", kind),
+        };
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for HyperTransportCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_msi_mapping() {
+        let mut dump = vec![0; 0x100];
+        dump[0xb8..0xbc].copy_from_slice(&0xa801_0008u32.to_le_bytes());
+        dump[0xbc..0xc0].copy_from_slice(&0xfee0_0000u32.to_le_bytes());
+
+        let cap =
+            HyperTransportCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0xb8).unwrap();
+
+        assert_eq!(cap.kind(), HyperTransportType::MsiMapping);
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "HyperTransport: MSI Mapping Enable+ Fixed-\n\
+             \t\tMapping Address Base: 00000000fee00000"
+        );
+    }
+
+    #[test]
+    fn test_end_of_config_space() {
+        let mut dump = vec![0; 0x100];
+        dump[0xf4..0xf8].copy_from_slice(&0x0022_0008u32.to_le_bytes());
+        dump[0xfc..0x100].copy_from_slice(&0xa800_0008u32.to_le_bytes());
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(DumpAccess::new(&dump)));
+
+        let primary = HyperTransportCapability::new(Rc::clone(&access), 0xf4).unwrap();
+        assert_eq!(
+            primary.cap_string(2).unwrap(),
+            "HyperTransport: Slave or Primary Interface"
+        );
+
+        let msi = HyperTransportCapability::new(access, 0xfc).unwrap();
+        assert_eq!(
+            msi.cap_string(2).unwrap(),
+            "HyperTransport: MSI Mapping Enable- Fixed-"
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flag;
+use super::Flags;
+use super::EXTENDED_CONFIG_SPACE_END;
+
+fn algorithm(algorithm: u32) -> &'static str {
+    match algorithm {
+        0 => "AES-GCM-256-96b",
+        _ => "reserved",
+    }
+}
+
+fn header_encryption(mode: u32) -> &'static str {
+    match mode {
+        0 => "no",
+        1 => "17:2",
+        2 => "25:2",
+        3 => "33:2",
+        4 => "41:2",
+        _ => "reserved",
+    }
+}
+
+fn aggregation(mode: u32) -> &'static str {
+    match mode & 0x3 {
+        0 => "-",
+        1 => "=2",
+        2 => "=4",
+        _ => "=8",
+    }
+}
+
+/// IDE stream state, as reported by a Link or Selective IDE Stream Status register.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdeStreamState {
+    Insecure,
+    Secure,
+    Reserved(u8),
+}
+
+impl IdeStreamState {
+    pub fn new(status: u32) -> IdeStreamState {
+        match status & 0xf {
+            0 => IdeStreamState::Insecure,
+            2 => IdeStreamState::Secure,
+            state => IdeStreamState::Reserved(state as u8),
+        }
+    }
+}
+
+impl Display for IdeStreamState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdeStreamState::Insecure => write!(f, "Insecure"),
+            IdeStreamState::Secure => write!(f, "Secure"),
+            IdeStreamState::Reserved(state) => write!(f, "reserved({})", state),
+        }
+    }
+}
+
+/// Fields shared by the Link and Selective IDE Stream Control registers.
+fn stream_control_string(control: u32, selective: bool) -> String {
+    let mut text = format!(
+        "{} NPR{} PR{} CPL{} {}",
+        Flag::new("En", control & 0x1 != 0),
+        aggregation(control >> 2),
+        aggregation(control >> 4),
+        aggregation(control >> 6),
+        Flag::new("PCRC", control & (1 << 8) != 0),
+    );
+
+    if selective {
+        text += &format!(" {}", Flag::new("CFG", control & (1 << 9) != 0));
+    }
+
+    text += &format!(
+        " HdrEnc={} Alg='{}' TC{} ID{}",
+        header_encryption((control >> 10) & 0xf),
+        algorithm((control >> 14) & 0x1f),
+        (control >> 19) & 0x7,
+        control >> 24
+    );
+
+    if selective && control & (1 << 22) != 0 {
+        text += " Default";
+    }
+
+    text
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkIdeStream {
+    pub control: u32,
+    pub status: u32,
+}
+
+impl LinkIdeStream {
+    pub fn enabled(&self) -> bool {
+        self.control & 0x1 != 0
+    }
+
+    pub fn stream_id(&self) -> u8 {
+        (self.control >> 24) as u8
+    }
+
+    pub fn state(&self) -> IdeStreamState {
+        IdeStreamState::new(self.status)
+    }
+}
+
+/// An IDE Address Association register block, with the memory range decoded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdeAddressAssociation {
+    pub valid: bool,
+    pub base: u64,
+    pub limit: u64,
+}
+
+impl IdeAddressAssociation {
+    pub fn new(registers: [u32; 3]) -> IdeAddressAssociation {
+        let [low, limit_upper, base_upper] = registers;
+
+        IdeAddressAssociation {
+            valid: low & 0x1 != 0,
+            base: (base_upper as u64) << 32 | (((low >> 8) & 0xfff) as u64) << 20,
+            limit: (limit_upper as u64) << 32 | ((low >> 20) as u64) << 20 | 0xfffff,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectiveIdeStream {
+    pub offset: u16,
+    pub capability: u32,
+    pub control: u32,
+    pub status: u32,
+    pub rid_association: (u32, u32),
+    pub address_association: Vec<IdeAddressAssociation>,
+}
+
+impl SelectiveIdeStream {
+    pub fn enabled(&self) -> bool {
+        self.control & 0x1 != 0
+    }
+
+    pub fn stream_id(&self) -> u8 {
+        (self.control >> 24) as u8
+    }
+
+    pub fn state(&self) -> IdeStreamState {
+        IdeStreamState::new(self.status)
+    }
+
+    pub fn rid_valid(&self) -> bool {
+        self.rid_association.1 & 0x1 != 0
+    }
+
+    pub fn rid_base(&self) -> u16 {
+        (self.rid_association.1 >> 8) as u16
+    }
+
+    pub fn rid_limit(&self) -> u16 {
+        (self.rid_association.0 >> 8) as u16
+    }
+
+    pub fn segment_base(&self) -> u8 {
+        (self.rid_association.1 >> 24) as u8
+    }
+}
+
+pub struct IdeCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+
+    capabilities: u32,
+    control: u32,
+    link_streams: Vec<LinkIdeStream>,
+    selective_streams: Vec<SelectiveIdeStream>,
+}
+
+impl IdeCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<IdeCapability> {
+        let raw = access.read(offset as u64 + 4, 8)?;
+        let capabilities = BinaryParser::le32(&raw, 0..4)?;
+
+        const END: u64 = EXTENDED_CONFIG_SPACE_END as u64;
+        let mut next = offset as u64 + 0x0c;
+
+        let mut link_streams = vec![];
+        if capabilities & 0x1 != 0 {
+            let count = (((capabilities >> 13) & 0x7) as usize + 1)
+                .min((END.saturating_sub(next) / 8) as usize);
+            let raw = access.read(next, count * 8)?;
+            for stream in 0..count {
+                let start = stream * 8;
+                link_streams.push(LinkIdeStream {
+                    control: BinaryParser::le32(&raw, start..start + 4)?,
+                    status: BinaryParser::le32(&raw, start + 4..start + 8)?,
+                });
+            }
+            next += count as u64 * 8;
+        }
+
+        let mut selective_streams = vec![];
+        if capabilities & 0x2 != 0 {
+            for _ in 0..((capabilities >> 16) & 0xff) + 1 {
+                // Streams that do not fit in config space are not decoded
+                if next + 20 > END {
+                    break;
+                }
+                let raw = access.read(next, 20)?;
+                let capability = BinaryParser::le32(&raw, 0..4)?;
+                let blocks = (capability & 0xf) as usize;
+                if next + 20 + blocks as u64 * 12 > END {
+                    break;
+                }
+
+                let addresses = access.read(next + 20, blocks * 12)?;
+                let mut address_association = vec![];
+                for block in 0..blocks {
+                    let start = block * 12;
+                    address_association.push(IdeAddressAssociation::new([
+                        BinaryParser::le32(&addresses, start..start + 4)?,
+                        BinaryParser::le32(&addresses, start + 4..start + 8)?,
+                        BinaryParser::le32(&addresses, start + 8..start + 12)?,
+                    ]));
+                }
+
+                selective_streams.push(SelectiveIdeStream {
+                    offset: next as u16,
+                    capability,
+                    control: BinaryParser::le32(&raw, 4..8)?,
+                    status: BinaryParser::le32(&raw, 8..12)?,
+                    rid_association: (
+                        BinaryParser::le32(&raw, 12..16)?,
+                        BinaryParser::le32(&raw, 16..20)?,
+                    ),
+                    address_association,
+                });
+                next += 20 + blocks as u64 * 12;
+            }
+        }
+
+        Ok(IdeCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities,
+            control: BinaryParser::le32(&raw, 4..8)?,
+            link_streams,
+            selective_streams,
+        })
+    }
+
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    pub fn control(&self) -> u32 {
+        self.control
+    }
+
+    pub fn link_streams(&self) -> &[LinkIdeStream] {
+        &self.link_streams
+    }
+
+    pub fn selective_streams(&self) -> &[SelectiveIdeStream] {
+        &self.selective_streams
+    }
+
+    fn link_streams_string(&self, verbosity: u8) -> String {
+        let mut text = String::new();
+        let mut register = self.offset + 0x0c;
+
+        for (index, stream) in self.link_streams.iter().enumerate() {
+            text += &format!(
+                "\t\t{}LinkIDE#{} Ctl: {}\n",
+                register_prefix(register, verbosity),
+                index,
+                stream_control_string(stream.control, false)
+            );
+            text += &format!(
+                "\t\t{}LinkIDE#{} Sta: Status={} {}\n",
+                register_prefix(register + 4, verbosity),
+                index,
+                stream.state(),
+                Flag::new("RecvChkFail", stream.status & (1 << 31) != 0)
+            );
+            register += 8;
+        }
+
+        text
+    }
+
+    fn selective_streams_string(&self, verbosity: u8) -> String {
+        let mut text = String::new();
+
+        for (index, stream) in self.selective_streams.iter().enumerate() {
+            text += &format!(
+                "\t\t{}SelectiveIDE#{} Cap: RID#={}\n",
+                register_prefix(stream.offset, verbosity),
+                index,
+                stream.address_association.len()
+            );
+            text += &format!(
+                "\t\t{}SelectiveIDE#{} Ctl: {}\n",
+                register_prefix(stream.offset + 4, verbosity),
+                index,
+                stream_control_string(stream.control, true)
+            );
+            text += &format!(
+                "\t\t{}SelectiveIDE#{} Sta: {} {}\n",
+                register_prefix(stream.offset + 8, verbosity),
+                index,
+                stream.state(),
+                Flag::new("RecvChkFail", stream.status & (1 << 31) != 0)
+            );
+            text += &format!(
+                "\t\t{}SelectiveIDE#{} RID: {} Base={:x} Limit={:x} SegBase={:x}\n",
+                register_prefix(stream.offset + 12, verbosity),
+                index,
+                Flag::new("Valid", stream.rid_valid()),
+                stream.rid_base(),
+                stream.rid_limit(),
+                stream.segment_base()
+            );
+
+            let mut register = stream.offset + 20;
+            for (block, address) in stream.address_association.iter().enumerate() {
+                text += &format!(
+                    "\t\t{}SelectiveIDE#{} RID#{}: {} Base={:x} Limit={:x}\n",
+                    register_prefix(register, verbosity),
+                    index,
+                    block,
+                    Flag::new("Valid", address.valid),
+                    address.base,
+                    address.limit
+                );
+                register += 12;
+            }
+        }
+
+        text
+    }
+}
+
+fn register_prefix(offset: u16, verbosity: u8) -> String {
+    match verbosity >= 3 {
+        true => format!("[{:x}] ", offset),
+        false => String::new(),
+    }
+}
+
+impl Capability for IdeCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Integrity & Data Encryption\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tIDECap: Lnk={} Sel={} {} Alg='{}' TCs={} {}\n",
+                self.link_streams.len(),
+                self.selective_streams.len(),
+                Flags::new(
+                    self.capabilities,
+                    &[
+                        ("FlowThru", 2),
+                        ("PartHdr", 3),
+                        ("Aggr", 4),
+                        ("PCRC", 5),
+                        ("IDE_KM", 6),
+                        ("SelCfg", 7)
+                    ]
+                ),
+                algorithm((self.capabilities >> 8) & 0x1f),
+                ((self.capabilities >> 13) & 0x7) + 1,
+                Flag::new("TeeLim", self.capabilities & (1 << 24) != 0)
+            );
+            text += &format!("\t\tIDECtl: {}\n", Flags::new(self.control, &[("FTEn", 2)]));
+            text += &self.link_streams_string(verbosity);
+            text += &self.selective_streams_string(verbosity);
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for IdeCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_streams_past_end_of_config_space() {
+        let mut dump = vec![0; 0x2000];
+        dump[0xfe0..0xfe4].copy_from_slice(&0x0001_0030u32.to_le_bytes());
+        // 256 selective streams, only the first one fits below 4K
+        dump[0xfe4..0xfe8].copy_from_slice(&0x00ff_0002u32.to_le_bytes());
+
+        let cap = IdeCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0xfe0).unwrap();
+
+        assert_eq!(cap.selective_streams().len(), 1);
+    }
+
+    #[test]
+    fn test_selective_stream() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x300..0x304].copy_from_slice(&0x0001_0030u32.to_le_bytes());
+        // One link stream and one selective stream with a single address block
+        dump[0x304..0x308].copy_from_slice(&0x0000_0043u32.to_le_bytes());
+        dump[0x30c..0x310].copy_from_slice(&0x0100_0001u32.to_le_bytes());
+        dump[0x310..0x314].copy_from_slice(&0x0000_0002u32.to_le_bytes());
+        dump[0x314..0x318].copy_from_slice(&0x0000_0001u32.to_le_bytes());
+        dump[0x318..0x31c].copy_from_slice(&0x0240_0205u32.to_le_bytes());
+        dump[0x31c..0x320].copy_from_slice(&0x8000_0000u32.to_le_bytes());
+        dump[0x320..0x324].copy_from_slice(&0x0001_ff00u32.to_le_bytes());
+        dump[0x324..0x328].copy_from_slice(&0x0001_0001u32.to_le_bytes());
+        dump[0x328..0x32c].copy_from_slice(&0x9008_0001u32.to_le_bytes());
+        dump[0x32c..0x330].copy_from_slice(&0x0000_0000u32.to_le_bytes());
+        dump[0x330..0x334].copy_from_slice(&0x0000_0000u32.to_le_bytes());
+
+        let cap = IdeCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x300).unwrap();
+
+        let stream = &cap.selective_streams()[0];
+        assert_eq!(stream.stream_id(), 2);
+        assert_eq!(stream.rid_base(), 0x0100);
+        assert_eq!(
+            stream.address_association,
+            [IdeAddressAssociation {
+                valid: true,
+                base: 0x8000_0000,
+                limit: 0x900f_ffff,
+            }]
+        );
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Integrity & Data Encryption\n\
+             \t\tIDECap: Lnk=1 Sel=1 FlowThru- PartHdr- Aggr- PCRC- IDE_KM+ SelCfg- Alg='AES-GCM-256-96b' TCs=1 TeeLim-\n\
+             \t\tIDECtl: FTEn-\n\
+             \t\tLinkIDE#0 Ctl: En+ NPR- PR- CPL- PCRC- HdrEnc=no Alg='AES-GCM-256-96b' TC0 ID1\n\
+             \t\tLinkIDE#0 Sta: Status=Secure RecvChkFail-\n\
+             \t\tSelectiveIDE#0 Cap: RID#=1\n\
+             \t\tSelectiveIDE#0 Ctl: En+ NPR=2 PR- CPL- PCRC- CFG+ HdrEnc=no Alg='AES-GCM-256-96b' TC0 ID2 Default\n\
+             \t\tSelectiveIDE#0 Sta: Insecure RecvChkFail+\n\
+             \t\tSelectiveIDE#0 RID: Valid+ Base=100 Limit=1ff SegBase=0\n\
+             \t\tSelectiveIDE#0 RID#0: Valid+ Base=80000000 Limit=900fffff"
+        );
+    }
+}
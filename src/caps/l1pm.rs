@@ -0,0 +1,190 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::ltr::latency_ns;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+const L1_SUBSTATES: &[(&str, u8)] = &[
+    ("PCI-PM_L1.2", 0),
+    ("PCI-PM_L1.1", 1),
+    ("ASPM_L1.2", 2),
+    ("ASPM_L1.1", 3),
+];
+
+pub struct L1PmSubstatesCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    capabilities: u32,
+    control1: u32,
+    control2: u32,
+}
+
+impl L1PmSubstatesCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<L1PmSubstatesCapability> {
+        let raw = access.read(offset as u64 + 4, 12)?;
+
+        Ok(L1PmSubstatesCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities: BinaryParser::le32(&raw, 0..4)?,
+            control1: BinaryParser::le32(&raw, 4..8)?,
+            control2: BinaryParser::le32(&raw, 8..12)?,
+        })
+    }
+
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    pub fn control1(&self) -> u32 {
+        self.control1
+    }
+
+    pub fn control2(&self) -> u32 {
+        self.control2
+    }
+
+    fn l1_2_supported(&self) -> bool {
+        self.capabilities & 0b101 != 0
+    }
+
+    pub fn port_common_mode_restore_time(&self) -> u32 {
+        (self.capabilities >> 8) & 0xff
+    }
+
+    pub fn port_t_power_on(&self) -> Option<u32> {
+        power_on_us(self.capabilities >> 16)
+    }
+
+    pub fn common_mode_restore_time(&self) -> u32 {
+        (self.control1 >> 8) & 0xff
+    }
+
+    pub fn ltr_l1_2_threshold(&self) -> Option<u64> {
+        latency_ns(
+            ((self.control1 >> 16) & 0x3ff).into(),
+            ((self.control1 >> 29) & 0x7) as u16,
+        )
+    }
+
+    pub fn t_power_on(&self) -> Option<u32> {
+        power_on_us(self.control2)
+    }
+}
+
+// T_PowerOn scale in bits 1:0 and value in bits 7:3, as laid out in both L1SubCap and L1SubCtl2.
+fn power_on_us(register: u32) -> Option<u32> {
+    let value = (register >> 3) & 0x1f;
+    match register & 0x3 {
+        0 => Some(value * 2),
+        1 => Some(value * 10),
+        2 => Some(value * 100),
+        _ => None,
+    }
+}
+
+fn time_string(time: Option<u32>) -> String {
+    match time {
+        Some(time) => format!("{}us", time),
+        None => "<reserved>".to_string(),
+    }
+}
+
+impl Capability for L1PmSubstatesCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "L1 PM Substates\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tL1SubCap: {} {}\n",
+                Flags::new(self.capabilities, L1_SUBSTATES),
+                Flags::new(self.capabilities, &[("L1_PM_Substates", 4)])
+            );
+            if self.l1_2_supported() {
+                text += &format!(
+                    "\t\t\t  PortCommonModeRestoreTime={}us PortTPowerOnTime={}\n",
+                    self.port_common_mode_restore_time(),
+                    time_string(self.port_t_power_on())
+                );
+            }
+
+            text += &format!(
+                "\t\tL1SubCtl1: {}\n",
+                Flags::new(self.control1, L1_SUBSTATES)
+            );
+            if self.l1_2_supported() {
+                text += &format!(
+                    "\t\t\t   T_CommonMode={}us",
+                    self.common_mode_restore_time()
+                );
+                if self.capabilities & (1 << 2) != 0 {
+                    text += &match self.ltr_l1_2_threshold() {
+                        Some(threshold) => format!(" LTR1.2_Threshold={}ns", threshold),
+                        None => " LTR1.2_Threshold=<reserved>".to_string(),
+                    };
+                }
+                text += "\n";
+            }
+
+            text += "\t\tL1SubCtl2:";
+            if self.l1_2_supported() {
+                text += &format!(" T_PwrOn={}", time_string(self.t_power_on()));
+            }
+            text += "\n";
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for L1PmSubstatesCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_l1_substates() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x200..0x204].copy_from_slice(&0x0001_001eu32.to_le_bytes());
+        // All substates, CommonModeRestoreTime 40us, T_PowerOn 5 * 2us
+        dump[0x204..0x208].copy_from_slice(&0x0028_281fu32.to_le_bytes());
+        // ASPM L1.2 enabled, T_CommonMode 40us, threshold 64 * 1024ns
+        dump[0x208..0x20c].copy_from_slice(&0x4040_2804u32.to_le_bytes());
+        // T_PwrOn 4 * 10us
+        dump[0x20c..0x210].copy_from_slice(&0x0000_0021u32.to_le_bytes());
+
+        let cap =
+            L1PmSubstatesCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x200).unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "L1 PM Substates\n\
+             \t\tL1SubCap: PCI-PM_L1.2+ PCI-PM_L1.1+ ASPM_L1.2+ ASPM_L1.1+ L1_PM_Substates+\n\
+             \t\t\t  PortCommonModeRestoreTime=40us PortTPowerOnTime=10us\n\
+             \t\tL1SubCtl1: PCI-PM_L1.2- PCI-PM_L1.1- ASPM_L1.2+ ASPM_L1.1-\n\
+             \t\t\t   T_CommonMode=40us LTR1.2_Threshold=65536ns\n\
+             \t\tL1SubCtl2: T_PwrOn=40us"
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::secondary_pcie::lanes;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+/// A Lane N Margining Control or Status register.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginingLaneRegister {
+    pub receiver: u8,
+    pub margin_type: u8,
+    pub usage_model: u8,
+    pub payload: u8,
+}
+
+impl MarginingLaneRegister {
+    pub fn new(receiver: u8, margin_type: u8, payload: u8) -> MarginingLaneRegister {
+        MarginingLaneRegister {
+            receiver,
+            margin_type,
+            usage_model: 0,
+            payload,
+        }
+    }
+
+    pub fn from_register(register: u16) -> MarginingLaneRegister {
+        MarginingLaneRegister {
+            receiver: (register & 0x7) as u8,
+            margin_type: ((register >> 3) & 0x7) as u8,
+            usage_model: ((register >> 6) & 0x1) as u8,
+            payload: (register >> 8) as u8,
+        }
+    }
+
+    pub fn register(&self) -> u16 {
+        (self.receiver & 0x7) as u16
+            | ((self.margin_type & 0x7) as u16) << 3
+            | ((self.usage_model & 0x1) as u16) << 6
+            | (self.payload as u16) << 8
+    }
+}
+
+impl Display for MarginingLaneRegister {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Rcvr {} Type {} UsageModel {} Payload {:0>2x}",
+            self.receiver, self.margin_type, self.usage_model, self.payload
+        )
+    }
+}
+
+pub struct LaneMarginingCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+
+    port_capabilities: u16,
+    port_status: u16,
+    lanes: Vec<(MarginingLaneRegister, MarginingLaneRegister)>,
+}
+
+impl LaneMarginingCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<LaneMarginingCapability> {
+        let lanes = lanes(&access)?;
+        let raw = access.read(offset as u64 + 4, 4 + lanes * 4)?;
+
+        let mut registers = vec![];
+        for lane in 0..lanes {
+            let start = 4 + lane * 4;
+            registers.push((
+                MarginingLaneRegister::from_register(BinaryParser::le16(&raw, start..start + 2)?),
+                MarginingLaneRegister::from_register(BinaryParser::le16(
+                    &raw,
+                    start + 2..start + 4,
+                )?),
+            ));
+        }
+
+        Ok(LaneMarginingCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            port_capabilities: BinaryParser::le16(&raw, 0..2)?,
+            port_status: BinaryParser::le16(&raw, 2..4)?,
+            lanes: registers,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<LaneMarginingCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_extended(0x0027)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn port_capabilities(&self) -> u16 {
+        self.port_capabilities
+    }
+
+    pub fn port_status(&self) -> u16 {
+        self.port_status
+    }
+
+    pub fn uses_driver_software(&self) -> bool {
+        self.port_capabilities & 0x1 != 0
+    }
+
+    pub fn ready(&self) -> bool {
+        self.port_status & 0x1 != 0 && (!self.uses_driver_software() || self.port_status & 0x2 != 0)
+    }
+
+    pub fn lane_control(&self, lane: usize) -> Option<MarginingLaneRegister> {
+        self.lanes.get(lane).map(|(control, _)| *control)
+    }
+
+    pub fn lane_status(&self, lane: usize) -> Option<MarginingLaneRegister> {
+        self.lanes.get(lane).map(|(_, status)| *status)
+    }
+
+    /// Config space offset of the Lane N Margining Control register.
+    pub fn lane_control_offset(offset: u16, lane: u8) -> u16 {
+        offset + 0x8 + lane as u16 * 4
+    }
+
+    /// Config space offset of the Lane N Margining Status register.
+    pub fn lane_status_offset(offset: u16, lane: u8) -> u16 {
+        Self::lane_control_offset(offset, lane) + 2
+    }
+}
+
+impl Capability for LaneMarginingCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Lane Margining at the Receiver\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tPortCap: {}\n",
+                Flags::new(self.port_capabilities.into(), &[("Uses Driver", 0)])
+            );
+            text += &format!(
+                "\t\tPortSta: {}\n",
+                Flags::new(
+                    self.port_status.into(),
+                    &[("MargReady", 0), ("MargSoftReady", 1)]
+                )
+            );
+
+            if verbosity >= 3 {
+                for (lane, (control, status)) in self.lanes.iter().enumerate() {
+                    text += &format!(
+                        "\t\tLane {}: Ctl: {}\n\t\t\tSta: {}\n",
+                        lane, control, status
+                    );
+                }
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for LaneMarginingCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
@@ -0,0 +1,125 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+pub struct LatencyToleranceReportingCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    max_snoop: u16,
+    max_no_snoop: u16,
+}
+
+impl LatencyToleranceReportingCapability {
+    pub fn new(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+    ) -> Result<LatencyToleranceReportingCapability> {
+        let raw = access.read(offset as u64 + 4, 4)?;
+
+        Ok(LatencyToleranceReportingCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            max_snoop: BinaryParser::le16(&raw, 0..2)?,
+            max_no_snoop: BinaryParser::le16(&raw, 2..4)?,
+        })
+    }
+
+    pub fn max_snoop_latency(&self) -> Option<u64> {
+        latency_ns(
+            (self.max_snoop & 0x3ff).into(),
+            (self.max_snoop >> 10) & 0x7,
+        )
+    }
+
+    pub fn max_no_snoop_latency(&self) -> Option<u64> {
+        latency_ns(
+            (self.max_no_snoop & 0x3ff).into(),
+            (self.max_no_snoop >> 10) & 0x7,
+        )
+    }
+}
+
+/// Converts a latency value and its scale, as used by LTR and the L1.2 threshold, to ns.
+pub fn latency_ns(value: u64, scale: u16) -> Option<u64> {
+    match scale {
+        0..=5 => Some(value << (5 * scale)),
+        _ => None,
+    }
+}
+
+fn latency_string(latency: Option<u64>) -> String {
+    match latency {
+        Some(latency) => format!("{}ns", latency),
+        None => "<reserved>".to_string(),
+    }
+}
+
+impl Capability for LatencyToleranceReportingCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Latency Tolerance Reporting\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tMax snoop latency: {}\n",
+                latency_string(self.max_snoop_latency())
+            );
+            text += &format!(
+                "\t\tMax no snoop latency: {}\n",
+                latency_string(self.max_no_snoop_latency())
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for LatencyToleranceReportingCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_latencies() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x200..0x204].copy_from_slice(&0x0001_0018u32.to_le_bytes());
+        // Max snoop 10 * 1024ns, max no snoop uses the reserved scale 6
+        dump[0x204..0x206].copy_from_slice(&0x080au16.to_le_bytes());
+        dump[0x206..0x208].copy_from_slice(&0x1803u16.to_le_bytes());
+
+        let cap = LatencyToleranceReportingCapability::new(
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            0x200,
+        )
+        .unwrap();
+
+        assert_eq!(cap.max_snoop_latency(), Some(10240));
+        assert_eq!(cap.max_no_snoop_latency(), None);
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Latency Tolerance Reporting\n\
+             \t\tMax snoop latency: 10240ns\n\
+             \t\tMax no snoop latency: <reserved>"
+        );
+    }
+}
@@ -1,17 +1,56 @@
 use crate::access::Access;
+use crate::bdf::BusDeviceFunction;
+use crate::caps::binary_parser::BinaryParser;
 use crate::error::Result;
 use std::collections::HashSet;
 use std::fmt::Display;
 use std::rc::Rc;
 
 use self::header::{CommonHeader, Header};
-use self::power_management::PowerManagementCapability;
+use self::registry::{CapabilityRegistry, ExtendedConstructor};
 use self::unknown::{UnknownCapability, UnknownExtendedCapability};
 
+pub mod acs;
+pub mod aer;
+pub mod af;
+pub mod agp;
+pub mod ari;
+pub mod ats;
 pub mod binary_parser;
+pub mod cxl;
+pub mod debug_port;
+pub mod dlf;
+pub mod doe;
+pub mod dpc;
+pub mod dsn;
+pub mod ea;
 pub mod header;
+pub mod hot_plug;
+pub mod hot_swap;
+pub mod hypertransport;
+pub mod ide;
+pub mod l1pm;
+pub mod lane_margining;
+pub mod ltr;
+pub mod msi;
+pub mod msix;
+pub mod pasid;
+pub mod pci_express;
+pub mod pcix;
+pub mod physical_layer;
 pub mod power_management;
+pub mod pri;
+pub mod ptm;
+pub mod registry;
+pub mod resizable_bar;
+pub mod secondary_pcie;
+pub mod slot_id;
+pub mod sriov;
+pub mod ssvid;
 pub mod unknown;
+pub mod vc;
+pub mod vendor_specific;
+pub mod vpd;
 
 pub struct Flag {
     name: &'static str,
@@ -38,19 +77,84 @@ impl Display for Flag {
     }
 }
 
+pub struct Flags {
+    flags: Vec<Flag>,
+}
+
+impl Flags {
+    pub fn new(register: u32, bits: &[(&'static str, u8)]) -> Self {
+        Self {
+            flags: bits
+                .iter()
+                .map(|(name, bit)| Flag::new(name, register & (1 << bit) != 0))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        self.flags.iter().find(|f| f.name == name).map(|f| f.value)
+    }
+}
+
+impl Display for Flags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flags: Vec<_> = self.flags.iter().map(|flag| flag.to_string()).collect();
+        write!(f, "{}", flags.join(" "))
+    }
+}
+
+/// A function on the path between a device and its root port that lacks controls a path-wide
+/// feature such as ACS or PTM depends on.
+#[derive(Debug, PartialEq)]
+pub struct PathViolation {
+    pub bdf: BusDeviceFunction,
+    pub missing: Vec<&'static str>,
+}
+
+impl Display for PathViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: missing {}", self.bdf, self.missing.join(" "))
+    }
+}
+
 pub trait Capability {
     fn cap_string(&self, _verbosity: u8) -> Result<String>;
     fn offset(&self) -> Result<u64>;
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(None)
+    }
 }
 
+/// End of the PCI Express extended configuration space.
+pub(crate) const EXTENDED_CONFIG_SPACE_END: u16 = 0x1000;
+
 pub struct CapabilityFactory {
     access: Rc<Box<dyn Access>>,
+    registry: Rc<CapabilityRegistry>,
 }
 
 impl CapabilityFactory {
+    const VSEC_ID: u16 = 0x000b;
+    const DVSEC_ID: u16 = 0x0023;
+
     pub fn new(access: Rc<Box<dyn Access>>) -> CapabilityFactory {
+        Self::with_registry(access, Rc::new(CapabilityRegistry::default()))
+    }
+
+    /// A factory with no decoders, for walking the capability lists with `find_trad` and
+    /// `find_extended` without building the default registry.
+    pub fn lookup(access: Rc<Box<dyn Access>>) -> CapabilityFactory {
+        Self::with_registry(access, Rc::new(CapabilityRegistry::empty()))
+    }
+
+    pub fn with_registry(
+        access: Rc<Box<dyn Access>>,
+        registry: Rc<CapabilityRegistry>,
+    ) -> CapabilityFactory {
         CapabilityFactory {
             access: Rc::clone(&access),
+            registry,
         }
     }
 
@@ -73,8 +177,33 @@ impl CapabilityFactory {
         Ok(capabilities)
     }
 
-    fn scan_trad(&self) -> Result<Vec<Box<dyn Capability>>> {
-        let mut capabilities = vec![];
+    pub fn find_trad(&self, id: u8) -> Result<Option<u8>> {
+        Ok(self
+            .trad_list()?
+            .into_iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, offset)| offset))
+    }
+
+    pub fn find_extended(&self, id: u16) -> Result<Option<u16>> {
+        Ok(self
+            .extended_list()?
+            .into_iter()
+            .find(|(i, _)| *i == id)
+            .map(|(_, offset)| offset))
+    }
+
+    pub fn find_all_extended(&self, id: u16) -> Result<Vec<u16>> {
+        Ok(self
+            .extended_list()?
+            .into_iter()
+            .filter(|(i, _)| *i == id)
+            .map(|(_, offset)| offset)
+            .collect())
+    }
+
+    fn trad_list(&self) -> Result<Vec<(u8, u8)>> {
+        let mut list = vec![];
         let mut seen = HashSet::from([0]);
 
         let mut offset: u8 = self.access.read(0x34, 1)?.pop().unwrap_or_default();
@@ -82,16 +211,15 @@ impl CapabilityFactory {
         while !seen.contains(&offset) {
             seen.insert(offset);
 
-            let id = UnknownCapability::id(&self.access, offset)?;
-            capabilities.push(self.new_trad(id, offset)?);
+            list.push((UnknownCapability::id(&self.access, offset)?, offset));
             offset = UnknownCapability::next(&self.access, offset)?;
         }
 
-        Ok(capabilities)
+        Ok(list)
     }
 
-    fn scan_extended(&self) -> Result<Vec<Box<dyn Capability>>> {
-        let mut capabilities = vec![];
+    fn extended_list(&self) -> Result<Vec<(u16, u16)>> {
+        let mut list = vec![];
         let mut seen = HashSet::from([0]);
 
         let mut offset = 0x100;
@@ -99,28 +227,90 @@ impl CapabilityFactory {
         while !seen.contains(&offset) {
             seen.insert(offset);
 
-            let id = UnknownExtendedCapability::id(&self.access, offset)?;
-            capabilities.push(self.new_extended(id, offset)?);
+            list.push((UnknownExtendedCapability::id(&self.access, offset)?, offset));
             offset = UnknownExtendedCapability::next(&self.access, offset)?;
         }
 
+        Ok(list)
+    }
+
+    fn scan_trad(&self) -> Result<Vec<Box<dyn Capability>>> {
+        let mut capabilities = vec![];
+
+        for (id, offset) in self.trad_list()? {
+            capabilities.push(self.new_trad(id, offset)?);
+        }
+
+        Ok(capabilities)
+    }
+
+    fn scan_extended(&self) -> Result<Vec<Box<dyn Capability>>> {
+        let mut capabilities = vec![];
+
+        for (id, offset) in self.extended_list()? {
+            capabilities.push(self.new_extended(id, offset)?);
+        }
+
         Ok(capabilities)
     }
 
+    // A decoder that fails leaves its capability shown as unknown instead of failing the scan.
     fn new_trad(&self, id: u8, offset: u8) -> Result<Box<dyn Capability>> {
-        match id {
-            0x1 => Ok(Box::new(PowerManagementCapability::new(
-                Rc::clone(&self.access),
-                offset,
-            )?)),
-            _ => Ok(Box::new(UnknownCapability::new(
-                Rc::clone(&self.access),
-                offset,
-            )?)),
+        if let Some(constructor) = self.registry.trad(id) {
+            match constructor(Rc::clone(&self.access), offset) {
+                Ok(capability) => return Ok(capability),
+                Err(error) => log::warn!(
+                    "Cannot decode capability {:0>2x} at {:x}: {}",
+                    id,
+                    offset,
+                    error.message
+                ),
+            }
         }
+
+        Ok(Box::new(UnknownCapability::new(
+            Rc::clone(&self.access),
+            offset,
+        )?))
+    }
+
+    fn extended_constructor(&self, id: u16, offset: u16) -> Result<Option<ExtendedConstructor>> {
+        let constructor = match id {
+            Self::VSEC_ID => {
+                let vendor = Header::new(&self.access.read(0, 0x40)?)?.vendor_id()?;
+                let vsec_id = BinaryParser::le16(&self.access.read(offset as u64 + 4, 2)?, 0..2)?;
+                self.registry.vsec(vendor, vsec_id)
+            }
+            Self::DVSEC_ID => {
+                let vendor = BinaryParser::le16(&self.access.read(offset as u64 + 4, 2)?, 0..2)?;
+                let dvsec_id = BinaryParser::le16(&self.access.read(offset as u64 + 8, 2)?, 0..2)?;
+                self.registry.dvsec(vendor, dvsec_id)
+            }
+            _ => None,
+        };
+
+        Ok(constructor.or(self.registry.extended(id)))
     }
 
-    fn new_extended(&self, _id: u16, offset: u16) -> Result<Box<dyn Capability>> {
+    fn new_extended(&self, id: u16, offset: u16) -> Result<Box<dyn Capability>> {
+        let capability = self
+            .extended_constructor(id, offset)
+            .and_then(|constructor| match constructor {
+                Some(constructor) => constructor(Rc::clone(&self.access), offset).map(Some),
+                None => Ok(None),
+            });
+
+        match capability {
+            Ok(Some(capability)) => return Ok(capability),
+            Ok(None) => (),
+            Err(error) => log::warn!(
+                "Cannot decode extended capability {:0>4x} at {:x}: {}",
+                id,
+                offset,
+                error.message
+            ),
+        }
+
         Ok(Box::new(UnknownExtendedCapability::new(
             Rc::clone(&self.access),
             offset,
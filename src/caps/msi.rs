@@ -0,0 +1,224 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use nom::sequence::tuple;
+use nom::IResult;
+use nom::{bits, streaming::take};
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flag;
+
+type IResultMessageControl<'a> = IResult<&'a [u8], (u8, u8, u8, u8, u8, u8, u8, u8)>;
+
+pub struct MsiCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    enable: Flag,
+    multiple_message_capable: u8,
+    multiple_message_enable: u8,
+    address_64bit: Flag,
+    per_vector_masking: Flag,
+
+    address: u64,
+    data: u16,
+    mask: Option<u32>,
+    pending: Option<u32>,
+}
+
+impl MsiCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<MsiCapability> {
+        let mut raw = access.read(offset as u64 + 2, 2)?;
+        // TODO check endianness
+        raw.reverse();
+        let (
+            _,
+            (
+                _reserved,
+                _extended_message_data_enable,
+                _extended_message_data_capable,
+                per_vector_masking,
+                address_64bit,
+                multiple_message_enable,
+                multiple_message_capable,
+                enable,
+            ),
+        ) = Self::parse(&raw).unwrap();
+
+        // The address/data pair and the mask/pending registers move depending on
+        // whether the function supports 64-bit message addresses.
+        let data_offset = if address_64bit != 0 { 0x08 } else { 0x04 };
+        let length = if per_vector_masking != 0 {
+            data_offset + 12
+        } else {
+            data_offset + 2
+        };
+        let body = access.read(offset as u64 + 4, length)?;
+        let address = if address_64bit != 0 {
+            (BinaryParser::le32(&body, 0x04..0x08)? as u64) << 32
+                | BinaryParser::le32(&body, 0x00..0x04)? as u64
+        } else {
+            BinaryParser::le32(&body, 0x00..0x04)? as u64
+        };
+        let data = BinaryParser::le16(&body, data_offset..data_offset + 2)?;
+        let (mask, pending) = if per_vector_masking != 0 {
+            (
+                Some(BinaryParser::le32(&body, data_offset + 4..data_offset + 8)?),
+                Some(BinaryParser::le32(
+                    &body,
+                    data_offset + 8..data_offset + 12,
+                )?),
+            )
+        } else {
+            (None, None)
+        };
+
+        Ok(MsiCapability {
+            _access: access,
+            offset,
+            enable: Flag::new("Enable", enable != 0),
+            multiple_message_capable,
+            multiple_message_enable,
+            address_64bit: Flag::new("64bit", address_64bit != 0),
+            per_vector_masking: Flag::new("Maskable", per_vector_masking != 0),
+            address,
+            data,
+            mask,
+            pending,
+        })
+    }
+
+    fn parse(input: &[u8]) -> IResultMessageControl<'_> {
+        bits::<_, _, nom::error::Error<(&[u8], usize)>, _, _>(tuple((
+            take(5usize), // Reserved
+            take(1usize), // Extended Message Data Enable
+            take(1usize), // Extended Message Data Capable
+            take(1usize), // Per-Vector Masking Capable
+            take(1usize), // 64 bit address capable
+            take(3usize), // Multiple Message Enable
+            take(3usize), // Multiple Message Capable
+            take(1usize), // MSI Enable
+        )))(input)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enable.value
+    }
+
+    pub fn vectors_capable(&self) -> u32 {
+        1 << self.multiple_message_capable
+    }
+
+    pub fn vectors_enabled(&self) -> u32 {
+        1 << self.multiple_message_enable
+    }
+
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    pub fn data(&self) -> u16 {
+        self.data
+    }
+
+    pub fn mask(&self) -> Option<u32> {
+        self.mask
+    }
+
+    pub fn pending(&self) -> Option<u32> {
+        self.pending
+    }
+}
+
+impl Capability for MsiCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!(
+            "MSI: {} Count={}/{} {} {}\n",
+            self.enable,
+            self.vectors_enabled(),
+            self.vectors_capable(),
+            self.per_vector_masking,
+            self.address_64bit
+        );
+
+        if verbosity >= 2 {
+            if self.address_64bit.value {
+                text += &format!("\t\tAddress: {:0>16x}", self.address);
+            } else {
+                text += &format!("\t\tAddress: {:0>8x}", self.address);
+            }
+            text += &format!("  Data: {:0>4x}\n", self.data);
+
+            if let (Some(mask), Some(pending)) = (self.mask, self.pending) {
+                text += &format!("\t\tMasking: {:0>8x}  Pending: {:0>8x}\n", mask, pending);
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for MsiCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    fn capability(raw: &[u8]) -> MsiCapability {
+        let mut dump = vec![0; 0x50];
+        dump.extend_from_slice(raw);
+        dump.resize(0x100, 0);
+
+        MsiCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x50).unwrap()
+    }
+
+    #[test]
+    fn test_end_of_config_space() {
+        let mut dump = vec![0; 0xf0];
+        dump.extend_from_slice(&[0x05, 0x00, 0x00, 0x00, 0x00, 0x10, 0xe0, 0xfe, 0x34, 0x12]);
+        dump.resize(0x100, 0);
+
+        let cap = MsiCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0xf0).unwrap();
+        assert_eq!(cap.address(), 0xfee0_1000);
+        assert_eq!(cap.data(), 0x1234);
+    }
+
+    #[test]
+    fn test_64bit() {
+        let cap = capability(&[
+            0x05, 0x70, 0x8b, 0x00, 0x58, 0x03, 0xe0, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x21, 0x00,
+        ]);
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "MSI: Enable+ Count=1/32 Maskable- 64bit+\n\t\tAddress: 00000000fee00358  Data: 0021"
+        );
+    }
+
+    #[test]
+    fn test_32bit_maskable() {
+        let cap = capability(&[
+            0x05, 0x70, 0x12, 0x01, 0x00, 0x10, 0xe0, 0xfe, 0x34, 0x12, 0x00, 0x00, 0xfe, 0xff,
+            0xff, 0xff, 0x01, 0x00, 0x00, 0x00,
+        ]);
+
+        assert_eq!(cap.vectors_capable(), 2);
+        assert_eq!(cap.vectors_enabled(), 2);
+        assert_eq!(cap.mask(), Some(0xffff_fffe));
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "MSI: Enable- Count=2/2 Maskable+ 64bit-\n\t\tAddress: fee01000  Data: 1234\n\t\tMasking: fffffffe  Pending: 00000001"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::access::Access;
+use crate::bar::BAR;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::header::{CommonHeader, Header};
+use crate::error::Result;
+use nom::sequence::tuple;
+use nom::IResult;
+use nom::{bits, streaming::take};
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flag;
+
+type IResultMessageControl<'a> = IResult<&'a [u8], (u8, u8, u8, u16)>;
+
+pub struct MsixCapability {
+    access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    enable: Flag,
+    function_mask: Flag,
+    table_size: u16,
+
+    table: MsixLocation,
+    pba: MsixLocation,
+}
+
+impl MsixCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<MsixCapability> {
+        let mut raw = access.read(offset as u64 + 2, 2)?;
+        // TODO check endianness
+        raw.reverse();
+        let (_, (enable, function_mask, _reserved, table_size)) = Self::parse(&raw).unwrap();
+
+        let body = access.read(offset as u64 + 4, 8)?;
+
+        Ok(MsixCapability {
+            access: Rc::clone(&access),
+            offset,
+            enable: Flag::new("Enable", enable != 0),
+            function_mask: Flag::new("Masked", function_mask != 0),
+            table_size: table_size + 1,
+            table: MsixLocation::new(BinaryParser::le32(&body, 0x00..0x04)?),
+            pba: MsixLocation::new(BinaryParser::le32(&body, 0x04..0x08)?),
+        })
+    }
+
+    fn parse(input: &[u8]) -> IResultMessageControl<'_> {
+        bits::<_, _, nom::error::Error<(&[u8], usize)>, _, _>(tuple((
+            take(1usize),  // MSI-X Enable
+            take(1usize),  // Function Mask
+            take(3usize),  // Reserved
+            take(11usize), // Table Size
+        )))(input)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enable.value
+    }
+
+    pub fn function_masked(&self) -> bool {
+        self.function_mask.value
+    }
+
+    pub fn table_size(&self) -> u16 {
+        self.table_size
+    }
+
+    pub fn table(&self) -> &MsixLocation {
+        &self.table
+    }
+
+    pub fn pba(&self) -> &MsixLocation {
+        &self.pba
+    }
+
+    pub fn table_bar(&self) -> Result<Option<BAR>> {
+        self.table.bar(&self.access)
+    }
+
+    pub fn pba_bar(&self) -> Result<Option<BAR>> {
+        self.pba.bar(&self.access)
+    }
+}
+
+impl Capability for MsixCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!(
+            "MSI-X: {} Count={} {}\n",
+            self.enable, self.table_size, self.function_mask
+        );
+
+        if verbosity >= 2 {
+            text += &format!("\t\tVector table: {}\n", self.table);
+            if verbosity >= 3 {
+                if let Some(bar) = self.table_bar()? {
+                    text += &format!("\t\t\t{}\n", bar);
+                }
+            }
+
+            text += &format!("\t\tPBA: {}\n", self.pba);
+            if verbosity >= 3 {
+                if let Some(bar) = self.pba_bar()? {
+                    text += &format!("\t\t\t{}\n", bar);
+                }
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for MsixCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+pub struct MsixLocation {
+    bir: u8,
+    offset: u32,
+}
+
+impl MsixLocation {
+    pub fn new(register: u32) -> MsixLocation {
+        MsixLocation {
+            bir: (register & 0b111) as u8,
+            offset: register & !0b111,
+        }
+    }
+
+    pub fn bir(&self) -> u8 {
+        self.bir
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    pub fn bar(&self, access: &Rc<Box<dyn Access>>) -> Result<Option<BAR>> {
+        Header::new(&access.read(0, 0x40)?)?.bar(self.bir)
+    }
+}
+
+impl Display for MsixLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "BAR={} offset={:0>8x}", self.bir, self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_table_bar() {
+        let mut dump = vec![0; 0x100];
+        // Type 0 header with a 64-bit BAR0 and a 32-bit BAR2
+        dump[0x10..0x14].copy_from_slice(&0x8060_0004u32.to_le_bytes());
+        dump[0x18..0x1c].copy_from_slice(&0x9000_0000u32.to_le_bytes());
+        dump[0xb0..0xbc].copy_from_slice(&[
+            0x11, 0x00, 0x08, 0x80, 0x00, 0x20, 0x00, 0x00, 0x02, 0x30, 0x00, 0x00,
+        ]);
+
+        let cap = MsixCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0xb0).unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "MSI-X: Enable+ Count=9 Masked-\n\t\tVector table: BAR=0 offset=00002000\n\t\tPBA: BAR=2 offset=00003000"
+        );
+        assert_eq!(cap.table_bar().unwrap().unwrap().address(), 0x8060_0000);
+        assert_eq!(cap.pba_bar().unwrap().unwrap().address(), 0x9000_0000);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct PasidCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    capabilities: u16,
+    control: u16,
+}
+
+impl PasidCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<PasidCapability> {
+        let raw = access.read(offset as u64 + 4, 4)?;
+
+        Ok(PasidCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities: BinaryParser::le16(&raw, 0..2)?,
+            control: BinaryParser::le16(&raw, 2..4)?,
+        })
+    }
+
+    pub fn capabilities(&self) -> u16 {
+        self.capabilities
+    }
+
+    pub fn control(&self) -> u16 {
+        self.control
+    }
+
+    pub fn max_pasid_width(&self) -> u8 {
+        ((self.capabilities >> 8) & 0x1f) as u8
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.control & 0x1 != 0
+    }
+}
+
+impl Capability for PasidCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Process Address Space ID (PASID)\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tPASIDCap: {}, Max PASID Width: {:0>2x}\n",
+                Flags::new(self.capabilities.into(), &[("Exec", 1), ("Priv", 2)]),
+                self.max_pasid_width()
+            );
+            text += &format!(
+                "\t\tPASIDCtl: {}\n",
+                Flags::new(
+                    self.control.into(),
+                    &[("Enable", 0), ("Exec", 1), ("Priv", 2)]
+                )
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for PasidCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_pasid() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x1d0..0x1d4].copy_from_slice(&0x0001_001bu32.to_le_bytes());
+        // Exec and Priv capable with 20-bit PASIDs, enabled without Exec or Priv
+        dump[0x1d4..0x1d6].copy_from_slice(&0x1406u16.to_le_bytes());
+        dump[0x1d6..0x1d8].copy_from_slice(&0x0001u16.to_le_bytes());
+
+        let cap = PasidCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x1d0).unwrap();
+
+        assert_eq!(cap.max_pasid_width(), 20);
+        assert!(cap.enabled());
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Process Address Space ID (PASID)\n\
+             \t\tPASIDCap: Exec+ Priv+, Max PASID Width: 14\n\
+             \t\tPASIDCtl: Enable+ Exec- Priv-"
+        );
+    }
+}
@@ -0,0 +1,953 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::CapabilityFactory;
+use super::Flag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DevicePortType {
+    Endpoint,
+    LegacyEndpoint,
+    RootPort,
+    UpstreamPort,
+    DownstreamPort,
+    PciExpressToPciBridge,
+    PciToPciExpressBridge,
+    RootComplexIntegratedEndpoint,
+    RootComplexEventCollector,
+    Unknown(u8),
+}
+
+impl DevicePortType {
+    pub fn new(port_type: u8) -> DevicePortType {
+        match port_type {
+            0x0 => DevicePortType::Endpoint,
+            0x1 => DevicePortType::LegacyEndpoint,
+            0x4 => DevicePortType::RootPort,
+            0x5 => DevicePortType::UpstreamPort,
+            0x6 => DevicePortType::DownstreamPort,
+            0x7 => DevicePortType::PciExpressToPciBridge,
+            0x8 => DevicePortType::PciToPciExpressBridge,
+            0x9 => DevicePortType::RootComplexIntegratedEndpoint,
+            0xa => DevicePortType::RootComplexEventCollector,
+            _ => DevicePortType::Unknown(port_type),
+        }
+    }
+
+    pub fn is_endpoint(&self) -> bool {
+        matches!(
+            self,
+            DevicePortType::Endpoint
+                | DevicePortType::LegacyEndpoint
+                | DevicePortType::RootComplexIntegratedEndpoint
+        )
+    }
+
+    pub fn is_downstream_port(&self) -> bool {
+        matches!(
+            self,
+            DevicePortType::RootPort | DevicePortType::DownstreamPort
+        )
+    }
+
+    pub fn has_link(&self) -> bool {
+        !matches!(
+            self,
+            DevicePortType::RootComplexIntegratedEndpoint
+                | DevicePortType::RootComplexEventCollector
+        )
+    }
+
+    pub fn has_root_registers(&self) -> bool {
+        matches!(
+            self,
+            DevicePortType::RootPort | DevicePortType::RootComplexEventCollector
+        )
+    }
+}
+
+impl Display for DevicePortType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DevicePortType::Endpoint => write!(f, "Endpoint"),
+            DevicePortType::LegacyEndpoint => write!(f, "Legacy Endpoint"),
+            DevicePortType::RootPort => write!(f, "Root Port"),
+            DevicePortType::UpstreamPort => write!(f, "Upstream Port"),
+            DevicePortType::DownstreamPort => write!(f, "Downstream Port"),
+            DevicePortType::PciExpressToPciBridge => write!(f, "PCI-Express to PCI/PCI-X Bridge"),
+            DevicePortType::PciToPciExpressBridge => write!(f, "PCI/PCI-X to PCI-Express Bridge"),
+            DevicePortType::RootComplexIntegratedEndpoint => {
+                write!(f, "Root Complex Integrated Endpoint")
+            }
+            DevicePortType::RootComplexEventCollector => write!(f, "Root Complex Event Collector"),
+            DevicePortType::Unknown(t) => write!(f, "Unknown type {}", t),
+        }
+    }
+}
+
+pub struct PciExpressCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+    raw: Vec<u8>,
+}
+
+impl PciExpressCapability {
+    const LENGTH: usize = 0x3c;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<PciExpressCapability> {
+        let raw = access.read(offset.into(), Self::LENGTH)?;
+
+        Ok(PciExpressCapability {
+            _access: access,
+            offset,
+            raw,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<PciExpressCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_trad(0x10)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn capabilities(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x02..0x04)
+    }
+
+    pub fn device_capabilities(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x04..0x08)
+    }
+
+    pub fn device_control(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x08..0x0a)
+    }
+
+    pub fn device_status(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x0a..0x0c)
+    }
+
+    pub fn link_capabilities(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x0c..0x10)
+    }
+
+    pub fn link_control(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x10..0x12)
+    }
+
+    pub fn link_status(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x12..0x14)
+    }
+
+    pub fn slot_capabilities(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x14..0x18)
+    }
+
+    pub fn slot_control(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x18..0x1a)
+    }
+
+    pub fn slot_status(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x1a..0x1c)
+    }
+
+    pub fn root_control(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x1c..0x1e)
+    }
+
+    pub fn root_capabilities(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x1e..0x20)
+    }
+
+    pub fn root_status(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x20..0x24)
+    }
+
+    pub fn device_capabilities2(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x24..0x28)
+    }
+
+    pub fn device_control2(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x28..0x2a)
+    }
+
+    pub fn link_capabilities2(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x2c..0x30)
+    }
+
+    pub fn link_control2(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x30..0x32)
+    }
+
+    pub fn link_status2(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x32..0x34)
+    }
+
+    pub fn version(&self) -> Result<u8> {
+        Ok((self.capabilities()? & 0xf) as u8)
+    }
+
+    pub fn device_port_type(&self) -> Result<DevicePortType> {
+        Ok(DevicePortType::new(
+            ((self.capabilities()? >> 4) & 0xf) as u8,
+        ))
+    }
+
+    pub fn slot_implemented(&self) -> Result<bool> {
+        Ok(self.capabilities()? & (1 << 8) != 0)
+    }
+
+    pub fn interrupt_message_number(&self) -> Result<u8> {
+        Ok(((self.capabilities()? >> 9) & 0x1f) as u8)
+    }
+
+    pub fn function_level_reset_capable(&self) -> Result<bool> {
+        Ok(self.device_capabilities()? & (1 << 28) != 0)
+    }
+
+    pub fn max_link_speed(&self) -> Result<u8> {
+        Ok((self.link_capabilities()? & 0xf) as u8)
+    }
+
+    pub fn max_link_width(&self) -> Result<u8> {
+        Ok(((self.link_capabilities()? >> 4) & 0x3f) as u8)
+    }
+
+    pub fn current_link_speed(&self) -> Result<u8> {
+        Ok((self.link_status()? & 0xf) as u8)
+    }
+
+    pub fn current_link_width(&self) -> Result<u8> {
+        Ok(((self.link_status()? >> 4) & 0x3f) as u8)
+    }
+
+    fn header_string(&self) -> Result<String> {
+        let port_type = self.device_port_type()?;
+        let slot = if port_type.is_downstream_port() {
+            format!(" ({})", Flag::new("Slot", self.slot_implemented()?))
+        } else {
+            String::new()
+        };
+
+        Ok(format!(
+            "Express (v{}) {}{}, MSI {:0>2x}",
+            self.version()?,
+            port_type,
+            slot,
+            self.interrupt_message_number()?
+        ))
+    }
+
+    fn device_string(&self) -> Result<String> {
+        let port_type = self.device_port_type()?;
+        let cap = self.device_capabilities()?;
+
+        let mut text = format!(
+            "\t\tDevCap:\tMaxPayload {} bytes, PhantFunc {}",
+            128 << (cap & 0x7),
+            (1 << ((cap >> 3) & 0x3)) - 1
+        );
+        if port_type.is_endpoint() {
+            text += &format!(
+                ", Latency L0s {}, L1 {}",
+                latency_l0s((cap >> 6) & 0x7),
+                latency_l1((cap >> 9) & 0x7)
+            );
+        }
+        text += &format!("\n\t\t\t{}", Flag::new("ExtTag", cap & (1 << 5) != 0));
+        if matches!(
+            port_type,
+            DevicePortType::Endpoint
+                | DevicePortType::LegacyEndpoint
+                | DevicePortType::UpstreamPort
+                | DevicePortType::PciExpressToPciBridge
+        ) {
+            text += &format!(
+                " {} {} {}",
+                Flag::new("AttnBtn", cap & (1 << 12) != 0),
+                Flag::new("AttnInd", cap & (1 << 13) != 0),
+                Flag::new("PwrInd", cap & (1 << 14) != 0)
+            );
+        }
+        text += &format!(" {}", Flag::new("RBE", cap & (1 << 15) != 0));
+        if port_type.is_endpoint() {
+            text += &format!(" {}", Flag::new("FLReset", cap & (1 << 28) != 0));
+        }
+        if matches!(
+            port_type,
+            DevicePortType::Endpoint
+                | DevicePortType::UpstreamPort
+                | DevicePortType::PciExpressToPciBridge
+        ) {
+            text += &format!(
+                " SlotPowerLimit {}W",
+                power_limit((cap >> 18) & 0xff, (cap >> 26) & 0x3)
+            );
+        }
+        text += "\n";
+
+        let ctl = self.device_control()?;
+        text += &format!(
+            "\t\tDevCtl:\t{} {} {} {}\n",
+            Flag::new("CorrErr", ctl & (1 << 0) != 0),
+            Flag::new("NonFatalErr", ctl & (1 << 1) != 0),
+            Flag::new("FatalErr", ctl & (1 << 2) != 0),
+            Flag::new("UnsupReq", ctl & (1 << 3) != 0)
+        );
+        text += &format!(
+            "\t\t\t{} {} {} {} {}",
+            Flag::new("RlxdOrd", ctl & (1 << 4) != 0),
+            Flag::new("ExtTag", ctl & (1 << 8) != 0),
+            Flag::new("PhantFunc", ctl & (1 << 9) != 0),
+            Flag::new("AuxPwr", ctl & (1 << 10) != 0),
+            Flag::new("NoSnoop", ctl & (1 << 11) != 0)
+        );
+        if port_type == DevicePortType::PciExpressToPciBridge {
+            text += &format!(" {}", Flag::new("BrConfRtry", ctl & (1 << 15) != 0));
+        }
+        if port_type.is_endpoint() && cap & (1 << 28) != 0 {
+            text += &format!(" {}", Flag::new("FLReset", ctl & (1 << 15) != 0));
+        }
+        text += &format!(
+            "\n\t\t\tMaxPayload {} bytes, MaxReadReq {} bytes\n",
+            128 << ((ctl >> 5) & 0x7),
+            128 << ((ctl >> 12) & 0x7)
+        );
+
+        let sta = self.device_status()?;
+        text += &format!(
+            "\t\tDevSta:\t{} {} {} {} {} {}\n",
+            Flag::new("CorrErr", sta & (1 << 0) != 0),
+            Flag::new("NonFatalErr", sta & (1 << 1) != 0),
+            Flag::new("FatalErr", sta & (1 << 2) != 0),
+            Flag::new("UnsupReq", sta & (1 << 3) != 0),
+            Flag::new("AuxPwr", sta & (1 << 4) != 0),
+            Flag::new("TransPend", sta & (1 << 5) != 0)
+        );
+
+        Ok(text)
+    }
+
+    fn link_string(&self) -> Result<String> {
+        let port_type = self.device_port_type()?;
+        let cap = self.link_capabilities()?;
+
+        let aspm = (cap >> 10) & 0x3;
+        let mut text = format!(
+            "\t\tLnkCap:\tPort #{}, Speed {}, Width x{}, ASPM {}",
+            cap >> 24,
+            link_speed(cap & 0xf),
+            (cap >> 4) & 0x3f,
+            aspm_support(aspm)
+        );
+        if aspm != 0 {
+            text += ", Exit Latency ";
+            if aspm & 0b01 != 0 {
+                text += &format!("L0s {}", latency_l0s((cap >> 12) & 0x7));
+            }
+            if aspm & 0b10 != 0 {
+                text += &format!(
+                    "{}L1 {}",
+                    if aspm & 0b01 != 0 { ", " } else { "" },
+                    latency_l1((cap >> 15) & 0x7)
+                );
+            }
+        }
+        text += &format!(
+            "\n\t\t\t{} {} {} {} {}\n",
+            Flag::new("ClockPM", cap & (1 << 18) != 0),
+            Flag::new("Surprise", cap & (1 << 19) != 0),
+            Flag::new("LLActRep", cap & (1 << 20) != 0),
+            Flag::new("BwNot", cap & (1 << 21) != 0),
+            Flag::new("ASPMOptComp", cap & (1 << 22) != 0)
+        );
+
+        let ctl = self.link_control()?;
+        text += &format!("\t\tLnkCtl:\tASPM {};", aspm_enabled(ctl & 0x3));
+        if matches!(
+            port_type,
+            DevicePortType::RootPort
+                | DevicePortType::Endpoint
+                | DevicePortType::LegacyEndpoint
+                | DevicePortType::PciExpressToPciBridge
+        ) {
+            text += &format!(" RCB {} bytes,", if ctl & (1 << 3) != 0 { 128 } else { 64 });
+        }
+        text += &format!(
+            " {} {}\n",
+            Flag::new("Disabled", ctl & (1 << 4) != 0),
+            Flag::new("CommClk", ctl & (1 << 6) != 0)
+        );
+        text += &format!(
+            "\t\t\t{} {} {} {} {}\n",
+            Flag::new("ExtSynch", ctl & (1 << 7) != 0),
+            Flag::new("ClockPM", ctl & (1 << 8) != 0),
+            Flag::new("AutWidDis", ctl & (1 << 9) != 0),
+            Flag::new("BWInt", ctl & (1 << 10) != 0),
+            Flag::new("AutBWInt", ctl & (1 << 11) != 0)
+        );
+
+        let sta = self.link_status()?;
+        text += &format!(
+            "\t\tLnkSta:\tSpeed {}{}, Width x{}{}\n",
+            link_speed((sta & 0xf).into()),
+            link_compare(port_type, (sta & 0xf).into(), cap & 0xf),
+            (sta >> 4) & 0x3f,
+            link_compare(port_type, ((sta >> 4) & 0x3f).into(), (cap >> 4) & 0x3f)
+        );
+        text += &format!(
+            "\t\t\t{} {} {} {} {} {}\n",
+            Flag::new("TrErr", sta & (1 << 10) != 0),
+            Flag::new("Train", sta & (1 << 11) != 0),
+            Flag::new("SlotClk", sta & (1 << 12) != 0),
+            Flag::new("DLActive", sta & (1 << 13) != 0),
+            Flag::new("BWMgmt", sta & (1 << 14) != 0),
+            Flag::new("ABWMgmt", sta & (1 << 15) != 0)
+        );
+
+        Ok(text)
+    }
+
+    fn slot_string(&self) -> Result<String> {
+        let cap = self.slot_capabilities()?;
+
+        let mut text = format!(
+            "\t\tSltCap:\t{} {} {} {} {} {} {}\n",
+            Flag::new("AttnBtn", cap & (1 << 0) != 0),
+            Flag::new("PwrCtrl", cap & (1 << 1) != 0),
+            Flag::new("MRL", cap & (1 << 2) != 0),
+            Flag::new("AttnInd", cap & (1 << 3) != 0),
+            Flag::new("PwrInd", cap & (1 << 4) != 0),
+            Flag::new("HotPlug", cap & (1 << 6) != 0),
+            Flag::new("Surprise", cap & (1 << 5) != 0)
+        );
+        text += &format!(
+            "\t\t\tSlot #{}, PowerLimit {}W; {} {}\n",
+            cap >> 19,
+            power_limit((cap >> 7) & 0xff, (cap >> 15) & 0x3),
+            Flag::new("Interlock", cap & (1 << 17) != 0),
+            Flag::new("NoCompl", cap & (1 << 18) != 0)
+        );
+
+        let ctl = self.slot_control()?;
+        text += &format!(
+            "\t\tSltCtl:\tEnable: {} {} {} {} {} {} {}\n",
+            Flag::new("AttnBtn", ctl & (1 << 0) != 0),
+            Flag::new("PwrFlt", ctl & (1 << 1) != 0),
+            Flag::new("MRL", ctl & (1 << 2) != 0),
+            Flag::new("PresDet", ctl & (1 << 3) != 0),
+            Flag::new("CmdCplt", ctl & (1 << 4) != 0),
+            Flag::new("HPIrq", ctl & (1 << 5) != 0),
+            Flag::new("LinkChg", ctl & (1 << 12) != 0)
+        );
+        text += &format!(
+            "\t\t\tControl: AttnInd {}, PwrInd {}, {} {}\n",
+            indicator((ctl >> 6) & 0x3),
+            indicator((ctl >> 8) & 0x3),
+            Flag::new("Power", ctl & (1 << 10) != 0),
+            Flag::new("Interlock", ctl & (1 << 11) != 0)
+        );
+
+        let sta = self.slot_status()?;
+        text += &format!(
+            "\t\tSltSta:\tStatus: {} {} {} {} {} {}\n",
+            Flag::new("AttnBtn", sta & (1 << 0) != 0),
+            Flag::new("PowerFlt", sta & (1 << 1) != 0),
+            Flag::new("MRL", sta & (1 << 5) != 0),
+            Flag::new("CmdCplt", sta & (1 << 4) != 0),
+            Flag::new("PresDet", sta & (1 << 6) != 0),
+            Flag::new("Interlock", sta & (1 << 7) != 0)
+        );
+        text += &format!(
+            "\t\t\tChanged: {} {} {}\n",
+            Flag::new("MRL", sta & (1 << 2) != 0),
+            Flag::new("PresDet", sta & (1 << 3) != 0),
+            Flag::new("LinkState", sta & (1 << 8) != 0)
+        );
+
+        Ok(text)
+    }
+
+    fn root_string(&self) -> Result<String> {
+        let cap = self.root_capabilities()?;
+        let mut text = format!(
+            "\t\tRootCap: {}\n",
+            Flag::new("CRSVisible", cap & (1 << 0) != 0)
+        );
+
+        let ctl = self.root_control()?;
+        text += &format!(
+            "\t\tRootCtl: {} {} {} {} {}\n",
+            Flag::new("ErrCorrectable", ctl & (1 << 0) != 0),
+            Flag::new("ErrNon-Fatal", ctl & (1 << 1) != 0),
+            Flag::new("ErrFatal", ctl & (1 << 2) != 0),
+            Flag::new("PMEIntEna", ctl & (1 << 3) != 0),
+            Flag::new("CRSVisible", ctl & (1 << 4) != 0)
+        );
+
+        let sta = self.root_status()?;
+        text += &format!(
+            "\t\tRootSta: PME ReqID {:0>4x}, {} {}\n",
+            sta & 0xffff,
+            Flag::new("PMEStatus", sta & (1 << 16) != 0),
+            Flag::new("PMEPending", sta & (1 << 17) != 0)
+        );
+
+        Ok(text)
+    }
+
+    fn device2_string(&self) -> Result<String> {
+        let port_type = self.device_port_type()?;
+        let cap = self.device_capabilities2()?;
+
+        let mut text = format!(
+            "\t\tDevCap2: Completion Timeout: {}, {} {} {}\n",
+            completion_timeout_ranges(cap & 0xf),
+            Flag::new("TimeoutDis", cap & (1 << 4) != 0),
+            Flag::new("NROPrPrP", cap & (1 << 10) != 0),
+            Flag::new("LTR", cap & (1 << 11) != 0)
+        );
+        text += &format!(
+            "\t\t\t {} {} OBFF {}, {} {}",
+            Flag::new("10BitTagComp", cap & (1 << 16) != 0),
+            Flag::new("10BitTagReq", cap & (1 << 17) != 0),
+            obff_support((cap >> 18) & 0x3),
+            Flag::new("ExtFmt", cap & (1 << 20) != 0),
+            Flag::new("EETLPPrefix", cap & (1 << 21) != 0)
+        );
+        if cap & (1 << 21) != 0 {
+            let prefixes = (cap >> 22) & 0x3;
+            text += &format!(
+                ", MaxEETLPPrefixes {}",
+                if prefixes == 0 { 4 } else { prefixes }
+            );
+        }
+        text += &format!(
+            "\n\t\t\t EmergencyPowerReduction {}, {}\n",
+            emergency_power_reduction((cap >> 24) & 0x3),
+            Flag::new("EmergencyPowerReductionInit", cap & (1 << 26) != 0)
+        );
+        text += &format!(
+            "\t\t\t {} {} {}",
+            Flag::new("FRS", cap & (1 << 31) != 0),
+            Flag::new("TPHComp", cap & (1 << 12) != 0),
+            Flag::new("ExtTPHComp", cap & (1 << 13) != 0)
+        );
+        if port_type.is_downstream_port() {
+            text += &format!(" {}", Flag::new("ARIFwd", cap & (1 << 5) != 0));
+        }
+        text += "\n";
+        if port_type.is_downstream_port()
+            || port_type.is_endpoint()
+            || port_type == DevicePortType::UpstreamPort
+        {
+            text += "\t\t\t AtomicOpsCap:";
+            if !port_type.is_endpoint() {
+                text += &format!(" {}", Flag::new("Routing", cap & (1 << 6) != 0));
+            }
+            if port_type == DevicePortType::RootPort || port_type.is_endpoint() {
+                text += &format!(
+                    " {} {} {}",
+                    Flag::new("32bit", cap & (1 << 7) != 0),
+                    Flag::new("64bit", cap & (1 << 8) != 0),
+                    Flag::new("128bitCAS", cap & (1 << 9) != 0)
+                );
+            }
+            text += "\n";
+        }
+
+        let ctl = self.device_control2()?;
+        text += &format!(
+            "\t\tDevCtl2: Completion Timeout: {}, {}",
+            completion_timeout_value(ctl & 0xf),
+            Flag::new("TimeoutDis", ctl & (1 << 4) != 0)
+        );
+        if port_type.is_downstream_port() {
+            text += &format!(" {}", Flag::new("ARIFwd", ctl & (1 << 5) != 0));
+        }
+        text += &format!(
+            " {} {} OBFF {},\n",
+            Flag::new("LTR", ctl & (1 << 10) != 0),
+            Flag::new("10BitTagReq", ctl & (1 << 12) != 0),
+            obff_enable((ctl >> 13) & 0x3)
+        );
+        text += "\t\t\t AtomicOpsCtl:";
+        if port_type == DevicePortType::RootPort || port_type.is_endpoint() {
+            text += &format!(" {}", Flag::new("ReqEn", ctl & (1 << 6) != 0));
+        }
+        if port_type.is_downstream_port() || port_type == DevicePortType::UpstreamPort {
+            text += &format!(" {}", Flag::new("EgressBlck", ctl & (1 << 7) != 0));
+        }
+        text += "\n";
+
+        Ok(text)
+    }
+
+    fn link2_string(&self) -> Result<String> {
+        let mut text = String::new();
+
+        let cap = self.link_capabilities2()?;
+        if cap != 0 {
+            text += &format!(
+                "\t\tLnkCap2: Supported Link Speeds: {}, {} {} {} {}\n",
+                supported_link_speeds((cap >> 1) & 0x7f),
+                Flag::new("Crosslink", cap & (1 << 8) != 0),
+                Flag::new("Retimer", cap & (1 << 23) != 0),
+                Flag::new("2Retimers", cap & (1 << 24) != 0),
+                Flag::new("DRS", cap & (1 << 31) != 0)
+            );
+        }
+
+        let ctl = self.link_control2()?;
+        text += &format!(
+            "\t\tLnkCtl2: Target Link Speed: {}, {} {}",
+            link_speed((ctl & 0xf).into()),
+            Flag::new("EnterCompliance", ctl & (1 << 4) != 0),
+            Flag::new("SpeedDis", ctl & (1 << 5) != 0)
+        );
+        if self.device_port_type()?.is_downstream_port() {
+            text += &format!(
+                ", Selectable De-emphasis: {}",
+                if ctl & (1 << 6) != 0 {
+                    "-3.5dB"
+                } else {
+                    "-6dB"
+                }
+            );
+        }
+        text += &format!(
+            "\n\t\t\t Transmit Margin: {}, {} {}\n",
+            transmit_margin((ctl >> 7) & 0x7),
+            Flag::new("EnterModifiedCompliance", ctl & (1 << 10) != 0),
+            Flag::new("ComplianceSOS", ctl & (1 << 11) != 0)
+        );
+        text += &format!(
+            "\t\t\t Compliance Preset/De-emphasis: {}\n",
+            transmitter_preset(((ctl >> 12) & 0xf) as u8)
+        );
+
+        let sta = self.link_status2()?;
+        text += &format!(
+            "\t\tLnkSta2: Current De-emphasis Level: {}, {} {}\n",
+            if sta & (1 << 0) != 0 {
+                "-3.5dB"
+            } else {
+                "-6dB"
+            },
+            Flag::new("EqualizationComplete", sta & (1 << 1) != 0),
+            Flag::new("EqualizationPhase1", sta & (1 << 2) != 0)
+        );
+        text += &format!(
+            "\t\t\t {} {} {}\n",
+            Flag::new("EqualizationPhase2", sta & (1 << 3) != 0),
+            Flag::new("EqualizationPhase3", sta & (1 << 4) != 0),
+            Flag::new("LinkEqualizationRequest", sta & (1 << 5) != 0)
+        );
+        text += &format!(
+            "\t\t\t {} {} CrosslinkRes: {}\n",
+            Flag::new("Retimer", sta & (1 << 6) != 0),
+            Flag::new("2Retimers", sta & (1 << 7) != 0),
+            crosslink_resolution((sta >> 8) & 0x3)
+        );
+
+        Ok(text)
+    }
+}
+
+impl Capability for PciExpressCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!("{}\n", self.header_string()?);
+
+        if verbosity >= 2 {
+            let port_type = self.device_port_type()?;
+
+            text += &self.device_string()?;
+            if port_type.has_link() {
+                text += &self.link_string()?;
+            }
+            if port_type.is_downstream_port() && self.slot_implemented()? {
+                text += &self.slot_string()?;
+            }
+            if port_type.has_root_registers() {
+                text += &self.root_string()?;
+            }
+            if self.version()? >= 2 {
+                text += &self.device2_string()?;
+                if port_type.has_link() {
+                    text += &self.link2_string()?;
+                }
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for PciExpressCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+pub fn link_speed(speed: u32) -> &'static str {
+    match speed {
+        1 => "2.5GT/s",
+        2 => "5GT/s",
+        3 => "8GT/s",
+        4 => "16GT/s",
+        5 => "32GT/s",
+        6 => "64GT/s",
+        _ => "unknown",
+    }
+}
+
+pub fn transmitter_preset(preset: u8) -> &'static str {
+    match preset {
+        0 => "-6dB de-emphasis, 0dB preshoot",
+        1 => "-3.5dB de-emphasis, 0dB preshoot",
+        2 => "-4.4dB de-emphasis, 0dB preshoot",
+        3 => "-2.5dB de-emphasis, 0dB preshoot",
+        4 => "0dB de-emphasis, 0dB preshoot",
+        5 => "0dB de-emphasis, 1.9dB preshoot",
+        6 => "0dB de-emphasis, 2.5dB preshoot",
+        7 => "-6.0dB de-emphasis, 3.5dB preshoot",
+        8 => "-3.5dB de-emphasis, 3.5dB preshoot",
+        9 => "0dB de-emphasis, 3.5dB preshoot",
+        _ => "Reserved",
+    }
+}
+
+fn link_compare(port_type: DevicePortType, status: u32, capability: u32) -> &'static str {
+    if status > capability {
+        " (overdriven)"
+    } else if status == capability {
+        " (ok)"
+    } else if port_type.is_downstream_port() {
+        ""
+    } else {
+        " (downgraded)"
+    }
+}
+
+fn latency_l0s(latency: u32) -> &'static str {
+    match latency {
+        0 => "<64ns",
+        1 => "<128ns",
+        2 => "<256ns",
+        3 => "<512ns",
+        4 => "<1us",
+        5 => "<2us",
+        6 => "<4us",
+        _ => "unlimited",
+    }
+}
+
+fn latency_l1(latency: u32) -> &'static str {
+    match latency {
+        0 => "<1us",
+        1 => "<2us",
+        2 => "<4us",
+        3 => "<8us",
+        4 => "<16us",
+        5 => "<32us",
+        6 => "<64us",
+        _ => "unlimited",
+    }
+}
+
+fn power_limit(value: u32, scale: u32) -> f64 {
+    if scale == 0 && value >= 0xf0 {
+        return match value {
+            0xf0 => 250.0,
+            0xf1 => 275.0,
+            0xf2 => 300.0,
+            _ => 0.0,
+        };
+    }
+
+    value as f64
+        * match scale {
+            0 => 1.0,
+            1 => 0.1,
+            2 => 0.01,
+            _ => 0.001,
+        }
+}
+
+fn aspm_support(aspm: u32) -> &'static str {
+    match aspm {
+        1 => "L0s",
+        2 => "L1",
+        3 => "L0s L1",
+        _ => "not supported",
+    }
+}
+
+fn aspm_enabled(aspm: u16) -> &'static str {
+    match aspm {
+        1 => "L0s Enabled",
+        2 => "L1 Enabled",
+        3 => "L0s L1 Enabled",
+        _ => "Disabled",
+    }
+}
+
+fn indicator(state: u16) -> &'static str {
+    match state {
+        1 => "On",
+        2 => "Blink",
+        3 => "Off",
+        _ => "Unknown",
+    }
+}
+
+fn completion_timeout_ranges(ranges: u32) -> &'static str {
+    match ranges {
+        0x0 => "Not Supported",
+        0x1 => "Range A",
+        0x2 => "Range B",
+        0x3 => "Range AB",
+        0x6 => "Range BC",
+        0x7 => "Range ABC",
+        0xe => "Range BCD",
+        0xf => "Range ABCD",
+        _ => "Unknown",
+    }
+}
+
+fn completion_timeout_value(value: u16) -> &'static str {
+    match value {
+        0x0 => "50us to 50ms",
+        0x1 => "50us to 100us",
+        0x2 => "1ms to 10ms",
+        0x5 => "16ms to 55ms",
+        0x6 => "65ms to 210ms",
+        0x9 => "260ms to 900ms",
+        0xa => "1s to 3.5s",
+        0xd => "4s to 13s",
+        0xe => "17s to 64s",
+        _ => "Unknown",
+    }
+}
+
+fn obff_support(obff: u32) -> &'static str {
+    match obff {
+        1 => "Via message",
+        2 => "Via WAKE#",
+        3 => "Via message/WAKE#",
+        _ => "Not Supported",
+    }
+}
+
+fn obff_enable(obff: u16) -> &'static str {
+    match obff {
+        1 => "Via message A",
+        2 => "Via message B",
+        3 => "Via WAKE#",
+        _ => "Disabled",
+    }
+}
+
+fn emergency_power_reduction(epr: u32) -> &'static str {
+    match epr {
+        1 => "Dev Specific",
+        2 => "Form Factor Dev Specific",
+        3 => "Reserved",
+        _ => "Not Supported",
+    }
+}
+
+fn supported_link_speeds(speeds: u32) -> &'static str {
+    if speeds & 0x20 != 0 {
+        "2.5-64GT/s"
+    } else if speeds & 0x10 != 0 {
+        "2.5-32GT/s"
+    } else if speeds & 0x08 != 0 {
+        "2.5-16GT/s"
+    } else if speeds & 0x04 != 0 {
+        "2.5-8GT/s"
+    } else if speeds & 0x02 != 0 {
+        "2.5-5GT/s"
+    } else if speeds & 0x01 != 0 {
+        "2.5GT/s"
+    } else {
+        "Unknown"
+    }
+}
+
+fn transmit_margin(margin: u16) -> &'static str {
+    match margin {
+        0 => "Normal Operating Range",
+        1 => "800-1200mV(full-swing)/400-700mV(half-swing)",
+        2..=5 => "200-400mV(full-swing)/100-200mV(half-swing)",
+        _ => "Unknown",
+    }
+}
+
+fn crosslink_resolution(resolution: u16) -> &'static str {
+    match resolution {
+        1 => "Upstream Port",
+        2 => "Downstream Port",
+        3 => "incomplete",
+        _ => "unsupported",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    fn capability(raw: &[u8]) -> PciExpressCapability {
+        let mut dump = vec![0; 0x70];
+        dump.extend_from_slice(raw);
+        dump.resize(0x100, 0);
+
+        PciExpressCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x70).unwrap()
+    }
+
+    #[test]
+    fn test_endpoint_header() {
+        let cap = capability(&[0x10, 0x00, 0x02, 0x00]);
+
+        assert_eq!(cap.device_port_type().unwrap(), DevicePortType::Endpoint);
+        assert_eq!(cap.cap_string(1).unwrap(), "Express (v2) Endpoint, MSI 00");
+    }
+
+    #[test]
+    fn test_root_port_header() {
+        let cap = capability(&[0x10, 0x00, 0x42, 0x03]);
+
+        assert_eq!(cap.device_port_type().unwrap(), DevicePortType::RootPort);
+        assert_eq!(
+            cap.cap_string(1).unwrap(),
+            "Express (v2) Root Port (Slot+), MSI 01"
+        );
+    }
+
+    #[test]
+    fn test_link_status() {
+        let mut raw = vec![0; 0x3c];
+        raw[0x02] = 0x02;
+        // LnkCap: 8GT/s x4
+        raw[0x0c..0x10].copy_from_slice(&0x0000_0443u32.to_le_bytes());
+        // LnkSta: 5GT/s x4
+        raw[0x12..0x14].copy_from_slice(&0x0042u16.to_le_bytes());
+        let cap = capability(&raw);
+
+        assert_eq!(cap.max_link_speed().unwrap(), 3);
+        assert_eq!(cap.max_link_width().unwrap(), 4);
+        assert!(cap
+            .cap_string(2)
+            .unwrap()
+            .contains("LnkSta:\tSpeed 5GT/s (downgraded), Width x4 (ok)"));
+    }
+}
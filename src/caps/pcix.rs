@@ -0,0 +1,204 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::header::Header;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+const MAX_OUTSTANDING: [u8; 8] = [1, 2, 3, 4, 8, 12, 16, 32];
+const SECONDARY_CLOCK: [&str; 8] = ["conv", "66MHz", "100MHz", "133MHz", "?4", "?5", "?6", "?7"];
+
+/// The PCI-X capability, laid out differently for devices and bridges.
+pub struct PciXCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    bridge: bool,
+    raw: Vec<u8>,
+}
+
+impl PciXCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<PciXCapability> {
+        let bridge = matches!(Header::new(&access.read(0, 0x40)?)?, Header::Type1(_));
+
+        Ok(PciXCapability {
+            raw: access.read(offset.into(), if bridge { 0x10 } else { 0x08 })?,
+            _access: access,
+            offset,
+            bridge,
+        })
+    }
+
+    pub fn is_bridge(&self) -> bool {
+        self.bridge
+    }
+
+    /// The command register of a device, or the secondary status register of a bridge.
+    pub fn command(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x02..0x04)
+    }
+
+    pub fn status(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x04..0x08)
+    }
+
+    pub fn upstream_split_transaction_control(&self) -> Result<Option<u32>> {
+        match self.bridge {
+            true => Ok(Some(BinaryParser::le32(&self.raw, 0x08..0x0c)?)),
+            false => Ok(None),
+        }
+    }
+
+    pub fn downstream_split_transaction_control(&self) -> Result<Option<u32>> {
+        match self.bridge {
+            true => Ok(Some(BinaryParser::le32(&self.raw, 0x0c..0x10)?)),
+            false => Ok(None),
+        }
+    }
+
+    fn requester(status: u32) -> String {
+        format!(
+            "Dev={:0>2x}:{:0>2x}.{}",
+            (status >> 8) & 0xff,
+            (status >> 3) & 0x1f,
+            status & 0x7
+        )
+    }
+
+    fn device_string(&self) -> Result<String> {
+        let command = self.command()? as u32;
+        let status = self.status()?;
+
+        let mut text = format!(
+            "\t\tCommand: {} RBC={} OST={}\n",
+            Flags::new(command, &[("DPERE", 0), ("ERO", 1)]),
+            1 << (9 + ((command >> 2) & 0x3)),
+            MAX_OUTSTANDING[((command >> 4) & 0x7) as usize]
+        );
+        text += &format!(
+            "\t\tStatus: {} {} DC={} DMMRBC={} DMOST={} DMCRS={} {}\n",
+            Self::requester(status),
+            Flags::new(
+                status,
+                &[("64bit", 16), ("133MHz", 17), ("SCD", 18), ("USC", 19)]
+            ),
+            match status & (1 << 20) != 0 {
+                true => "bridge",
+                false => "simple",
+            },
+            1 << (9 + ((status >> 21) & 0x3)),
+            MAX_OUTSTANDING[((status >> 23) & 0x7) as usize],
+            1 << (3 + ((status >> 26) & 0x7)),
+            Flags::new(status, &[("RSCEM", 29), ("266MHz", 30), ("533MHz", 31)])
+        );
+
+        Ok(text)
+    }
+
+    fn bridge_string(&self) -> Result<String> {
+        let secondary = self.command()? as u32;
+        let status = self.status()?;
+
+        let mut text = format!(
+            "\t\tSecondary Status: {} Freq={}\n",
+            Flags::new(
+                secondary,
+                &[
+                    ("64bit", 0),
+                    ("133MHz", 1),
+                    ("SCD", 2),
+                    ("USC", 3),
+                    ("SCO", 4),
+                    ("SRD", 5)
+                ]
+            ),
+            SECONDARY_CLOCK[((secondary >> 6) & 0x7) as usize]
+        );
+        text += &format!(
+            "\t\tStatus: {} {}\n",
+            Self::requester(status),
+            Flags::new(
+                status,
+                &[
+                    ("64bit", 16),
+                    ("133MHz", 17),
+                    ("SCD", 18),
+                    ("USC", 19),
+                    ("SCO", 20),
+                    ("SRD", 21)
+                ]
+            )
+        );
+
+        for (name, control) in [
+            ("Upstream", self.upstream_split_transaction_control()?),
+            ("Downstream", self.downstream_split_transaction_control()?),
+        ] {
+            let control = control.unwrap_or_default();
+            text += &format!(
+                "\t\t{}: Capacity={} CommitmentLimit={}\n",
+                name,
+                control & 0xffff,
+                control >> 16
+            );
+        }
+
+        Ok(text)
+    }
+}
+
+impl Capability for PciXCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = match self.bridge {
+            true => "PCI-X bridge device\n",
+            false => "PCI-X non-bridge device\n",
+        }
+        .to_string();
+
+        if verbosity >= 2 {
+            text += &match self.bridge {
+                true => self.bridge_string()?,
+                false => self.device_string()?,
+            };
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for PciXCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_device() {
+        let mut dump = vec![0; 0x100];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x60;
+        dump[0x60..0x64].copy_from_slice(&0x0022_0007u32.to_le_bytes());
+        dump[0x64..0x68].copy_from_slice(&0x0243_0208u32.to_le_bytes());
+
+        let cap = PciXCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x60).unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "PCI-X non-bridge device\n\
+             \t\tCommand: DPERE- ERO+ RBC=512 OST=3\n\
+             \t\tStatus: Dev=02:01.0 64bit+ 133MHz+ SCD- USC- DC=simple DMMRBC=2048 DMOST=8 DMCRS=8 RSCEM- 266MHz- 533MHz-"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::secondary_pcie::{lanes, LaneEqualization};
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+const EQUALIZATION_STATUS: &[(&str, u8)] = &[
+    ("EquComplete", 0),
+    ("EquPhase1", 1),
+    ("EquPhase2", 2),
+    ("EquPhase3", 3),
+    ("LinkEquRequest", 4),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataRate {
+    Gt16,
+    Gt32,
+    Gt64,
+}
+
+impl DataRate {
+    fn lane_equalization_offset(&self) -> u64 {
+        match self {
+            DataRate::Gt16 | DataRate::Gt32 => 0x20,
+            DataRate::Gt64 => 0x10,
+        }
+    }
+}
+
+impl Display for DataRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            DataRate::Gt16 => "16.0 GT/s",
+            DataRate::Gt32 => "32.0 GT/s",
+            DataRate::Gt64 => "64.0 GT/s",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// The Physical Layer 16.0, 32.0 and 64.0 GT/s extended capabilities.
+pub struct PhysicalLayerCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    rate: DataRate,
+
+    raw: Vec<u8>,
+    lane_equalization: Vec<LaneEqualization>,
+}
+
+impl PhysicalLayerCapability {
+    const LENGTH: usize = 0x20;
+
+    pub fn new(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+        rate: DataRate,
+    ) -> Result<PhysicalLayerCapability> {
+        let lanes = lanes(&access)?;
+        let equalization = access.read(offset as u64 + rate.lane_equalization_offset(), lanes)?;
+
+        Ok(PhysicalLayerCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            raw: access.read(offset.into(), Self::LENGTH)?,
+            _access: access,
+            offset,
+            rate,
+            lane_equalization: equalization
+                .into_iter()
+                .map(LaneEqualization::from_16gt)
+                .collect(),
+        })
+    }
+
+    pub fn rate(&self) -> DataRate {
+        self.rate
+    }
+
+    pub fn capabilities(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x04..0x08)
+    }
+
+    pub fn control(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x08..0x0c)
+    }
+
+    pub fn status(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x0c..0x10)
+    }
+
+    pub fn equalization_complete(&self) -> Result<bool> {
+        Ok(self.status()? & 0x1 != 0)
+    }
+
+    /// Local, first retimer and second retimer data parity mismatch status of the 16.0 GT/s
+    /// capability, one bit per lane.
+    pub fn data_parity_mismatch(&self) -> Result<Option<(u32, u32, u32)>> {
+        if self.rate != DataRate::Gt16 {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            BinaryParser::le32(&self.raw, 0x10..0x14)?,
+            BinaryParser::le32(&self.raw, 0x14..0x18)?,
+            BinaryParser::le32(&self.raw, 0x18..0x1c)?,
+        )))
+    }
+
+    pub fn lane_equalization(&self) -> &[LaneEqualization] {
+        &self.lane_equalization
+    }
+
+    fn registers_string(&self) -> Result<String> {
+        let status = self.status()?;
+
+        Ok(match self.rate {
+            DataRate::Gt16 => {
+                let (local, first, second) = self.data_parity_mismatch()?.unwrap_or_default();
+                format!(
+                    "\t\tPhy16Sta: {}\n\t\tPhy16DPMSta: Local={:0>8x} Retimer1={:0>8x} Retimer2={:0>8x}\n",
+                    Flags::new(status, EQUALIZATION_STATUS),
+                    local,
+                    first,
+                    second
+                )
+            }
+            DataRate::Gt32 => {
+                let cap = self.capabilities()?;
+                let ctl = self.control()?;
+                let mut text = format!(
+                    "\t\t32GTsCap: {}\n",
+                    Flags::new(
+                        cap,
+                        &[
+                            ("EqualizationBypass", 0),
+                            ("NoEqualizationNeeded", 1),
+                            ("ModTsMode0", 8),
+                            ("ModTsMode1", 9),
+                            ("ModTsMode2", 10)
+                        ]
+                    )
+                );
+                text += &format!(
+                    "\t\t32GTsCtl: {} ModTsMode: {}\n",
+                    Flags::new(
+                        ctl,
+                        &[("EqualizationBypassDis", 0), ("NoEqualizationNeededDis", 1)]
+                    ),
+                    (ctl >> 8) & 0x7
+                );
+                text += &format!(
+                    "\t\t32GTsSta: {} {} ReceivedEnhancedLinkControls: {} {}\n",
+                    Flags::new(status, EQUALIZATION_STATUS),
+                    Flags::new(status, &[("ModTsRecv", 5)]),
+                    (status >> 6) & 0x3,
+                    Flags::new(
+                        status,
+                        &[
+                            ("TxPrecodingOn", 8),
+                            ("TxPrecodeRequest", 9),
+                            ("NoEqualizationNeededRecv", 10)
+                        ]
+                    )
+                );
+                text
+            }
+            DataRate::Gt64 => format!(
+                "\t\t64GTsSta: {} {}\n",
+                Flags::new(status, EQUALIZATION_STATUS),
+                Flags::new(
+                    status,
+                    &[
+                        ("TxPrecodingOn", 5),
+                        ("TxPrecodeRequest", 6),
+                        ("NoEqualizationNeededRecv", 7)
+                    ]
+                )
+            ),
+        })
+    }
+}
+
+impl Capability for PhysicalLayerCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!("Physical Layer {}\n", self.rate);
+
+        if verbosity >= 2 {
+            text += &self.registers_string()?;
+
+            for (lane, equalization) in self.lane_equalization.iter().enumerate() {
+                text += &format!("\t\tLane {}: {}\n", lane, equalization.to_string(verbosity));
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for PhysicalLayerCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_64gt_presets() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x40;
+        // PCI Express endpoint with a x1 link
+        dump[0x40..0x44].copy_from_slice(&[0x10, 0x00, 0x02, 0x00]);
+        dump[0x4c..0x50].copy_from_slice(&0x0000_0016u32.to_le_bytes());
+        dump[0x400..0x404].copy_from_slice(&0x0001_0031u32.to_le_bytes());
+        dump[0x40c..0x410].copy_from_slice(&0x0000_002fu32.to_le_bytes());
+        dump[0x410] = 0x95;
+
+        let cap = PhysicalLayerCapability::new(
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            0x400,
+            DataRate::Gt64,
+        )
+        .unwrap();
+
+        assert!(cap.equalization_complete().unwrap());
+        assert_eq!(
+            cap.cap_string(3).unwrap(),
+            "Physical Layer 64.0 GT/s\n\
+             \t\t64GTsSta: EquComplete+ EquPhase1+ EquPhase2+ EquPhase3+ LinkEquRequest- TxPrecodingOn+ TxPrecodeRequest- NoEqualizationNeededRecv-\n\
+             \t\tLane 0: DsTxPreset=P5 UsTxPreset=P9\n\
+             \t\t\tDsTx: 0dB de-emphasis, 1.9dB preshoot\n\
+             \t\t\tUsTx: 0dB de-emphasis, 3.5dB preshoot"
+        );
+    }
+}
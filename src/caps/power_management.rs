@@ -1,4 +1,6 @@
 use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::CapabilityFactory;
 use crate::error::Result;
 use nom::sequence::tuple;
 use nom::IResult;
@@ -12,7 +14,7 @@ use super::Flag;
 type IResultCapability<'a> = IResult<&'a [u8], (u8, u8, u8, u8, u8, u8, u8, u8)>;
 
 pub struct PowerManagementCapability {
-    _access: Rc<Box<dyn Access>>,
+    access: Rc<Box<dyn Access>>,
     offset: u8,
 
     version: u8,
@@ -44,7 +46,7 @@ impl PowerManagementCapability {
             ),
         ) = Self::parse(&raw).unwrap();
         Ok(PowerManagementCapability {
-            _access: access,
+            access,
             offset,
             pme_support: PmeSupport::new(pme_support),
             d2_support: Flag::new("D2", d2_support != 0),
@@ -60,7 +62,24 @@ impl PowerManagementCapability {
         })
     }
 
-    fn parse(input: &[u8]) -> IResultCapability {
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<PowerManagementCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_trad(0x01)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn control_status(&self) -> Result<u16> {
+        BinaryParser::le16(&self.access.read(self.offset as u64 + 4, 2)?, 0..2)
+    }
+
+    /// Whether the function keeps its state across D3hot->D0, so that the transition does not
+    /// reset it.
+    pub fn no_soft_reset(&self) -> Result<bool> {
+        Ok(self.control_status()? & (1 << 3) != 0)
+    }
+
+    fn parse(input: &[u8]) -> IResultCapability<'_> {
         bits::<_, _, nom::error::Error<(&[u8], usize)>, _, _>(tuple((
             take(5usize), // PME_Support
             take(1usize), // D2_Support
@@ -0,0 +1,116 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct PageRequestInterfaceCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    raw: Vec<u8>,
+}
+
+impl PageRequestInterfaceCapability {
+    const LENGTH: usize = 0x10;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<PageRequestInterfaceCapability> {
+        Ok(PageRequestInterfaceCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            raw: access.read(offset.into(), Self::LENGTH)?,
+            _access: access,
+            offset,
+        })
+    }
+
+    pub fn control(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x04..0x06)
+    }
+
+    pub fn status(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x06..0x08)
+    }
+
+    pub fn outstanding_capacity(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x08..0x0c)
+    }
+
+    pub fn outstanding_allocation(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x0c..0x10)
+    }
+}
+
+impl Capability for PageRequestInterfaceCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Page Request Interface (PRI)\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tPRICtl: {}\n",
+                Flags::new(self.control()?.into(), &[("Enable", 0), ("Reset", 1)])
+            );
+            text += &format!(
+                "\t\tPRISta: {}\n",
+                Flags::new(
+                    self.status()?.into(),
+                    &[("RF", 0), ("UPRGI", 1), ("Stopped", 8), ("PASID", 15)]
+                )
+            );
+            text += &format!(
+                "\t\tPage Request Capacity: {:0>8x}, Page Request Allocation: {:0>8x}\n",
+                self.outstanding_capacity()?,
+                self.outstanding_allocation()?
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for PageRequestInterfaceCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_pri() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x1c0..0x1c4].copy_from_slice(&0x0001_0013u32.to_le_bytes());
+        // Enabled and stopped with PASID required in responses
+        dump[0x1c4..0x1c6].copy_from_slice(&0x0001u16.to_le_bytes());
+        dump[0x1c6..0x1c8].copy_from_slice(&0x8100u16.to_le_bytes());
+        dump[0x1c8..0x1cc].copy_from_slice(&0x0000_0200u32.to_le_bytes());
+        dump[0x1cc..0x1d0].copy_from_slice(&0x0000_0020u32.to_le_bytes());
+
+        let cap =
+            PageRequestInterfaceCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x1c0)
+                .unwrap();
+
+        assert_eq!(cap.outstanding_capacity().unwrap(), 0x200);
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Page Request Interface (PRI)\n\
+             \t\tPRICtl: Enable+ Reset-\n\
+             \t\tPRISta: RF- UPRGI- Stopped+ PASID+\n\
+             \t\tPage Request Capacity: 00000200, Page Request Allocation: 00000020"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::pci_express::{DevicePortType, PciExpressCapability};
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use crate::function::Function;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::PathViolation;
+
+pub struct PrecisionTimeMeasurementCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    capabilities: u32,
+    control: u32,
+}
+
+impl PrecisionTimeMeasurementCapability {
+    pub fn new(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+    ) -> Result<PrecisionTimeMeasurementCapability> {
+        let raw = access.read(offset as u64 + 4, 8)?;
+
+        Ok(PrecisionTimeMeasurementCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            capabilities: BinaryParser::le32(&raw, 0..4)?,
+            control: BinaryParser::le32(&raw, 4..8)?,
+        })
+    }
+
+    pub fn find(
+        access: &Rc<Box<dyn Access>>,
+    ) -> Result<Option<PrecisionTimeMeasurementCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_extended(0x001f)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn capabilities(&self) -> u32 {
+        self.capabilities
+    }
+
+    pub fn control(&self) -> u32 {
+        self.control
+    }
+
+    pub fn requester_capable(&self) -> bool {
+        self.capabilities & (1 << 0) != 0
+    }
+
+    pub fn responder_capable(&self) -> bool {
+        self.capabilities & (1 << 1) != 0
+    }
+
+    pub fn root_capable(&self) -> bool {
+        self.capabilities & (1 << 2) != 0
+    }
+
+    pub fn local_clock_granularity(&self) -> u8 {
+        (self.capabilities >> 8) as u8
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.control & (1 << 0) != 0
+    }
+
+    pub fn root_selected(&self) -> bool {
+        self.control & (1 << 1) != 0
+    }
+
+    pub fn effective_granularity(&self) -> u8 {
+        (self.control >> 8) as u8
+    }
+}
+
+fn granularity_string(granularity: u8, zero: &str) -> String {
+    match granularity {
+        0 => zero.to_string(),
+        255 => "Greater than 254ns".to_string(),
+        granularity => format!("{}ns", granularity),
+    }
+}
+
+fn flag(value: bool) -> char {
+    if value {
+        '+'
+    } else {
+        '-'
+    }
+}
+
+impl Capability for PrecisionTimeMeasurementCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Precision Time Measurement\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tPTMCap: Requester:{} Responder:{} Root:{}\n",
+                flag(self.requester_capable()),
+                flag(self.responder_capable()),
+                flag(self.root_capable())
+            );
+            text += &format!(
+                "\t\tPTMClockGranularity: {}\n",
+                granularity_string(self.local_clock_granularity(), "Unimplemented")
+            );
+            text += &format!(
+                "\t\tPTMControl: Enabled:{} RootSelected:{}\n",
+                flag(self.enabled()),
+                flag(self.root_selected())
+            );
+            text += &format!(
+                "\t\tPTMEffectiveGranularity: {}\n",
+                granularity_string(self.effective_granularity(), "Unknown")
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for PrecisionTimeMeasurementCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+/// Checks that PTM is enabled along `path`, which starts with the PTM requester and continues
+/// with its upstream bridges up to and including the root port. PTM works end to end when no
+/// violations are returned.
+///
+/// Switch downstream ports do not implement PTM, their upstream port does on behalf of the
+/// switch. The root port must be enabled and selected as the PTM root.
+pub fn path_violations(path: &[Function]) -> Result<Vec<PathViolation>> {
+    let mut violations = vec![];
+
+    for (i, function) in path.iter().enumerate() {
+        let port_type = match PciExpressCapability::find(&function.access())? {
+            Some(express) => express.device_port_type()?,
+            None => {
+                violations.push(PathViolation {
+                    bdf: *function.bdf(),
+                    missing: vec!["PCIe"],
+                });
+                continue;
+            }
+        };
+
+        if port_type == DevicePortType::DownstreamPort {
+            continue;
+        }
+
+        let mut missing = vec![];
+        match PrecisionTimeMeasurementCapability::find(&function.access())? {
+            None => missing.push("PTM"),
+            Some(ptm) => {
+                if i == 0 && !ptm.requester_capable() {
+                    missing.push("Requester");
+                }
+                if !ptm.enabled() {
+                    missing.push("Enabled");
+                }
+                if port_type == DevicePortType::RootPort && !ptm.root_selected() {
+                    missing.push("RootSelected");
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            violations.push(PathViolation {
+                bdf: *function.bdf(),
+                missing,
+            });
+        }
+    }
+
+    Ok(violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+    use crate::bdf::BusDeviceFunction;
+    use crate::kernel::Kernel;
+    use std::str::FromStr;
+
+    fn function(bdf: &str, port_type: u8, ptm: Option<(u32, u32)>) -> Function {
+        let mut dump = vec![0; 0x1000];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x40;
+        dump[0x40..0x44].copy_from_slice(&[0x10, 0x00, 0x02 | port_type << 4, 0x00]);
+        if let Some((capabilities, control)) = ptm {
+            dump[0x100..0x104].copy_from_slice(&0x0001_001fu32.to_le_bytes());
+            dump[0x104..0x108].copy_from_slice(&capabilities.to_le_bytes());
+            dump[0x108..0x10c].copy_from_slice(&control.to_le_bytes());
+        }
+
+        Function::new(
+            BusDeviceFunction::from_str(bdf).unwrap(),
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            Kernel,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_path_violations() {
+        let path = [
+            function("0000:03:00.0", 0x0, Some((0x0000_0801, 0x0000_0801))),
+            function("0000:02:01.0", 0x6, None),
+            function("0000:01:00.0", 0x5, Some((0x0000_0003, 0x0000_0000))),
+            function("0000:00:1c.0", 0x4, Some((0x0000_0107, 0x0000_0001))),
+        ];
+
+        let violations: Vec<_> = path_violations(&path)
+            .unwrap()
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect();
+        assert_eq!(
+            violations,
+            ["01:00.0: missing Enabled", "00:1c.0: missing RootSelected"]
+        );
+
+        assert_eq!(
+            PrecisionTimeMeasurementCapability::find(&path[0].access())
+                .unwrap()
+                .unwrap()
+                .cap_string(2)
+                .unwrap(),
+            "Precision Time Measurement\n\
+             \t\tPTMCap: Requester:+ Responder:- Root:-\n\
+             \t\tPTMClockGranularity: 8ns\n\
+             \t\tPTMControl: Enabled:+ RootSelected:-\n\
+             \t\tPTMEffectiveGranularity: 8ns"
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use crate::access::Access;
+use crate::error::Result;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::acs::AcsCapability;
+use super::aer::AdvancedErrorReportingCapability;
+use super::af::AdvancedFeaturesCapability;
+use super::agp::AgpCapability;
+use super::ari::AriCapability;
+use super::ats::AddressTranslationServicesCapability;
+use super::cxl;
+use super::debug_port::DebugPortCapability;
+use super::dlf::DataLinkFeatureCapability;
+use super::doe::DataObjectExchangeCapability;
+use super::dpc::DownstreamPortContainmentCapability;
+use super::dsn::DeviceSerialNumberCapability;
+use super::ea::EnhancedAllocationCapability;
+use super::hot_plug::HotPlugCapability;
+use super::hot_swap::CompactPciHotSwapCapability;
+use super::hypertransport::HyperTransportCapability;
+use super::ide::IdeCapability;
+use super::l1pm::L1PmSubstatesCapability;
+use super::lane_margining::LaneMarginingCapability;
+use super::ltr::LatencyToleranceReportingCapability;
+use super::msi::MsiCapability;
+use super::msix::MsixCapability;
+use super::pasid::PasidCapability;
+use super::pci_express::PciExpressCapability;
+use super::pcix::PciXCapability;
+use super::physical_layer::{DataRate, PhysicalLayerCapability};
+use super::power_management::PowerManagementCapability;
+use super::pri::PageRequestInterfaceCapability;
+use super::ptm::PrecisionTimeMeasurementCapability;
+use super::resizable_bar::ResizableBarCapability;
+use super::secondary_pcie::SecondaryPciExpressCapability;
+use super::slot_id::SlotIdCapability;
+use super::sriov::SriovCapability;
+use super::ssvid::BridgeSubsystemCapability;
+use super::vc::VirtualChannelCapability;
+use super::vendor_specific::{DesignatedVendorSpecificCapability, VendorSpecificCapability};
+use super::vpd::VitalProductDataCapability;
+use super::Capability;
+
+pub type TradConstructor = fn(Rc<Box<dyn Access>>, u8) -> Result<Box<dyn Capability>>;
+pub type ExtendedConstructor = fn(Rc<Box<dyn Access>>, u16) -> Result<Box<dyn Capability>>;
+
+/// Maps capability IDs to the constructors of their decoders.
+///
+/// Vendor-Specific (VSEC) decoders are keyed by the function's vendor ID and the VSEC ID,
+/// Designated Vendor-Specific (DVSEC) decoders by the DVSEC vendor ID and the DVSEC ID. IDs
+/// without a registered decoder are shown as unknown capabilities.
+pub struct CapabilityRegistry {
+    trad: HashMap<u8, TradConstructor>,
+    extended: HashMap<u16, ExtendedConstructor>,
+    vsec: HashMap<(u16, u16), ExtendedConstructor>,
+    dvsec: HashMap<(u16, u16), ExtendedConstructor>,
+}
+
+impl CapabilityRegistry {
+    pub fn empty() -> CapabilityRegistry {
+        CapabilityRegistry {
+            trad: HashMap::new(),
+            extended: HashMap::new(),
+            vsec: HashMap::new(),
+            dvsec: HashMap::new(),
+        }
+    }
+
+    pub fn register_trad(&mut self, id: u8, constructor: TradConstructor) -> &mut Self {
+        self.trad.insert(id, constructor);
+        self
+    }
+
+    pub fn register_extended(&mut self, id: u16, constructor: ExtendedConstructor) -> &mut Self {
+        self.extended.insert(id, constructor);
+        self
+    }
+
+    pub fn register_vsec(
+        &mut self,
+        vendor: u16,
+        id: u16,
+        constructor: ExtendedConstructor,
+    ) -> &mut Self {
+        self.vsec.insert((vendor, id), constructor);
+        self
+    }
+
+    pub fn register_dvsec(
+        &mut self,
+        vendor: u16,
+        id: u16,
+        constructor: ExtendedConstructor,
+    ) -> &mut Self {
+        self.dvsec.insert((vendor, id), constructor);
+        self
+    }
+
+    pub fn trad(&self, id: u8) -> Option<TradConstructor> {
+        self.trad.get(&id).copied()
+    }
+
+    pub fn extended(&self, id: u16) -> Option<ExtendedConstructor> {
+        self.extended.get(&id).copied()
+    }
+
+    pub fn vsec(&self, vendor: u16, id: u16) -> Option<ExtendedConstructor> {
+        self.vsec.get(&(vendor, id)).copied()
+    }
+
+    pub fn dvsec(&self, vendor: u16, id: u16) -> Option<ExtendedConstructor> {
+        self.dvsec.get(&(vendor, id)).copied()
+    }
+}
+
+impl Default for CapabilityRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry
+            .register_trad(0x01, |access, offset| {
+                Ok(Box::new(PowerManagementCapability::new(access, offset)?))
+            })
+            .register_trad(0x02, |access, offset| {
+                Ok(Box::new(AgpCapability::new(access, offset)?))
+            })
+            .register_trad(0x03, |access, offset| {
+                Ok(Box::new(VitalProductDataCapability::new(access, offset)?))
+            })
+            .register_trad(0x04, |access, offset| {
+                Ok(Box::new(SlotIdCapability::new(access, offset)?))
+            })
+            .register_trad(0x05, |access, offset| {
+                Ok(Box::new(MsiCapability::new(access, offset)?))
+            })
+            .register_trad(0x06, |access, offset| {
+                Ok(Box::new(CompactPciHotSwapCapability::new(access, offset)?))
+            })
+            .register_trad(0x07, |access, offset| {
+                Ok(Box::new(PciXCapability::new(access, offset)?))
+            })
+            .register_trad(0x08, |access, offset| {
+                Ok(Box::new(HyperTransportCapability::new(access, offset)?))
+            })
+            .register_trad(0x0a, |access, offset| {
+                Ok(Box::new(DebugPortCapability::new(access, offset)?))
+            })
+            .register_trad(0x0c, |access, offset| {
+                Ok(Box::new(HotPlugCapability::new(access, offset)?))
+            })
+            .register_trad(0x0d, |access, offset| {
+                Ok(Box::new(BridgeSubsystemCapability::new(access, offset)?))
+            })
+            .register_trad(0x10, |access, offset| {
+                Ok(Box::new(PciExpressCapability::new(access, offset)?))
+            })
+            .register_trad(0x11, |access, offset| {
+                Ok(Box::new(MsixCapability::new(access, offset)?))
+            })
+            .register_trad(0x13, |access, offset| {
+                Ok(Box::new(AdvancedFeaturesCapability::new(access, offset)?))
+            })
+            .register_trad(0x14, |access, offset| {
+                Ok(Box::new(EnhancedAllocationCapability::new(access, offset)?))
+            });
+
+        registry
+            .register_extended(0x0001, |access, offset| {
+                Ok(Box::new(AdvancedErrorReportingCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0002, |access, offset| {
+                Ok(Box::new(VirtualChannelCapability::new(access, offset)?))
+            })
+            .register_extended(0x0003, |access, offset| {
+                Ok(Box::new(DeviceSerialNumberCapability::new(access, offset)?))
+            })
+            .register_extended(0x0008, |access, offset| {
+                Ok(Box::new(VirtualChannelCapability::new_multi_function(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0009, |access, offset| {
+                Ok(Box::new(VirtualChannelCapability::new(access, offset)?))
+            })
+            .register_extended(0x000b, |access, offset| {
+                Ok(Box::new(VendorSpecificCapability::new(access, offset)?))
+            })
+            .register_extended(0x000d, |access, offset| {
+                Ok(Box::new(AcsCapability::new(access, offset)?))
+            })
+            .register_extended(0x000e, |access, offset| {
+                Ok(Box::new(AriCapability::new(access, offset)?))
+            })
+            .register_extended(0x000f, |access, offset| {
+                Ok(Box::new(AddressTranslationServicesCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0010, |access, offset| {
+                Ok(Box::new(SriovCapability::new(access, offset)?))
+            })
+            .register_extended(0x0013, |access, offset| {
+                Ok(Box::new(PageRequestInterfaceCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0015, |access, offset| {
+                Ok(Box::new(ResizableBarCapability::new(access, offset)?))
+            })
+            .register_extended(0x0018, |access, offset| {
+                Ok(Box::new(LatencyToleranceReportingCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0019, |access, offset| {
+                Ok(Box::new(SecondaryPciExpressCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x001b, |access, offset| {
+                Ok(Box::new(PasidCapability::new(access, offset)?))
+            })
+            .register_extended(0x001d, |access, offset| {
+                Ok(Box::new(DownstreamPortContainmentCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x001e, |access, offset| {
+                Ok(Box::new(L1PmSubstatesCapability::new(access, offset)?))
+            })
+            .register_extended(0x001f, |access, offset| {
+                Ok(Box::new(PrecisionTimeMeasurementCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0023, |access, offset| {
+                Ok(Box::new(DesignatedVendorSpecificCapability::new(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0024, |access, offset| {
+                Ok(Box::new(ResizableBarCapability::new_virtual(
+                    access, offset,
+                )?))
+            })
+            .register_extended(0x0025, |access, offset| {
+                Ok(Box::new(DataLinkFeatureCapability::new(access, offset)?))
+            })
+            .register_extended(0x0026, |access, offset| {
+                Ok(Box::new(PhysicalLayerCapability::new(
+                    access,
+                    offset,
+                    DataRate::Gt16,
+                )?))
+            })
+            .register_extended(0x0027, |access, offset| {
+                Ok(Box::new(LaneMarginingCapability::new(access, offset)?))
+            })
+            .register_extended(0x002a, |access, offset| {
+                Ok(Box::new(PhysicalLayerCapability::new(
+                    access,
+                    offset,
+                    DataRate::Gt32,
+                )?))
+            })
+            .register_extended(0x002e, |access, offset| {
+                Ok(Box::new(DataObjectExchangeCapability::new(access, offset)?))
+            })
+            .register_extended(0x0030, |access, offset| {
+                Ok(Box::new(IdeCapability::new(access, offset)?))
+            })
+            .register_extended(0x0031, |access, offset| {
+                Ok(Box::new(PhysicalLayerCapability::new(
+                    access,
+                    offset,
+                    DataRate::Gt64,
+                )?))
+            });
+
+        registry
+            .register_dvsec(
+                cxl::CXL_VENDOR_ID,
+                cxl::CXL_DEVICE_DVSEC_ID,
+                |access, offset| {
+                    Ok(Box::new(DesignatedVendorSpecificCapability::with_decoder(
+                        access,
+                        offset,
+                        "CXL",
+                        cxl::device_string,
+                    )?))
+                },
+            )
+            .register_dvsec(
+                cxl::CXL_VENDOR_ID,
+                cxl::CXL_PORT_DVSEC_ID,
+                |access, offset| {
+                    Ok(Box::new(DesignatedVendorSpecificCapability::with_decoder(
+                        access,
+                        offset,
+                        "CXL",
+                        cxl::port_string,
+                    )?))
+                },
+            )
+            .register_dvsec(
+                cxl::CXL_VENDOR_ID,
+                cxl::CXL_GPF_PORT_DVSEC_ID,
+                |access, offset| {
+                    Ok(Box::new(DesignatedVendorSpecificCapability::with_decoder(
+                        access,
+                        offset,
+                        "CXL",
+                        cxl::gpf_port_string,
+                    )?))
+                },
+            )
+            .register_dvsec(
+                cxl::CXL_VENDOR_ID,
+                cxl::CXL_GPF_DEVICE_DVSEC_ID,
+                |access, offset| {
+                    Ok(Box::new(DesignatedVendorSpecificCapability::with_decoder(
+                        access,
+                        offset,
+                        "CXL",
+                        cxl::gpf_device_string,
+                    )?))
+                },
+            )
+            .register_dvsec(
+                cxl::CXL_VENDOR_ID,
+                cxl::CXL_FLEX_BUS_PORT_DVSEC_ID,
+                |access, offset| {
+                    Ok(Box::new(DesignatedVendorSpecificCapability::with_decoder(
+                        access,
+                        offset,
+                        "CXL",
+                        cxl::flex_bus_port_string,
+                    )?))
+                },
+            )
+            .register_dvsec(
+                cxl::CXL_VENDOR_ID,
+                cxl::CXL_REGISTER_LOCATOR_DVSEC_ID,
+                |access, offset| {
+                    Ok(Box::new(DesignatedVendorSpecificCapability::with_decoder(
+                        access,
+                        offset,
+                        "CXL",
+                        cxl::register_locator_string,
+                    )?))
+                },
+            );
+
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+    use crate::caps::CapabilityFactory;
+    use crate::error::Error;
+
+    struct TestCapability {
+        offset: u64,
+    }
+
+    impl Capability for TestCapability {
+        fn cap_string(&self, _verbosity: u8) -> Result<String> {
+            Ok("Test".to_string())
+        }
+
+        fn offset(&self) -> Result<u64> {
+            Ok(self.offset)
+        }
+    }
+
+    #[test]
+    fn test_registered_decoders() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x40;
+        dump[0x40..0x42].copy_from_slice(&[0x09, 0x00]);
+        // DVSEC for vendor 0x1234, ID 0x0005 followed by an unknown extended capability
+        dump[0x100..0x104].copy_from_slice(&0x2001_0023u32.to_le_bytes());
+        dump[0x104..0x106].copy_from_slice(&0x1234u16.to_le_bytes());
+        dump[0x108..0x10a].copy_from_slice(&0x0005u16.to_le_bytes());
+        dump[0x200..0x204].copy_from_slice(&0x0001_0042u32.to_le_bytes());
+
+        let mut registry = CapabilityRegistry::empty();
+        registry
+            .register_trad(0x09, |_, offset| {
+                Ok(Box::new(TestCapability {
+                    offset: offset.into(),
+                }))
+            })
+            .register_dvsec(0x1234, 0x0005, |_, offset| {
+                Ok(Box::new(TestCapability {
+                    offset: offset.into(),
+                }))
+            });
+
+        let capabilities = CapabilityFactory::with_registry(
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            Rc::new(registry),
+        )
+        .scan()
+        .unwrap();
+
+        let text: Vec<_> = capabilities
+            .iter()
+            .map(|c| c.cap_string(0).unwrap())
+            .collect();
+        assert_eq!(text, ["Test", "Test", "Capability 0x42 at 0x200"]);
+    }
+
+    #[test]
+    fn test_failing_decoder() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x40;
+        dump[0x40..0x42].copy_from_slice(&[0x09, 0x50]);
+        dump[0x50..0x52].copy_from_slice(&[0x0a, 0x00]);
+        dump[0x100..0x104].copy_from_slice(&0x2001_0042u32.to_le_bytes());
+        dump[0x200..0x204].copy_from_slice(&0x0001_0043u32.to_le_bytes());
+
+        let mut registry = CapabilityRegistry::empty();
+        registry
+            .register_trad(0x09, |_, _| Err(Error::unknown_capability(0x09)))
+            .register_trad(0x0a, |_, offset| {
+                Ok(Box::new(TestCapability {
+                    offset: offset.into(),
+                }))
+            })
+            .register_extended(0x0042, |_, _| Err(Error::unknown_capability(0x42)));
+
+        let capabilities = CapabilityFactory::with_registry(
+            Rc::new(Box::new(DumpAccess::new(&dump))),
+            Rc::new(registry),
+        )
+        .scan()
+        .unwrap();
+
+        let text: Vec<_> = capabilities
+            .iter()
+            .map(|c| c.cap_string(0).unwrap())
+            .collect();
+        assert_eq!(
+            text,
+            [
+                "Capability 0x9 at 0x40",
+                "Test",
+                "Capability 0x42 at 0x100",
+                "Capability 0x43 at 0x200"
+            ]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use crate::access::Access;
+use crate::bar::BAR;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::header::{CommonHeader, Header};
+use crate::caps::sriov::SriovCapability;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+pub struct ResizableBarEntry {
+    offset: u16,
+    capability: u32,
+    control: u32,
+}
+
+impl ResizableBarEntry {
+    pub fn capability(&self) -> u32 {
+        self.capability
+    }
+
+    pub fn control(&self) -> u32 {
+        self.control
+    }
+
+    /// Config space offset of the control register of this entry.
+    pub fn control_offset(&self) -> u16 {
+        self.offset + 4
+    }
+
+    pub fn index(&self) -> u8 {
+        (self.control & 0x7) as u8
+    }
+
+    /// Sizes in bytes the BAR can be resized to, from 1MB up to 128TB.
+    pub fn supported_sizes(&self) -> Vec<u64> {
+        (4..32)
+            .filter(|bit| self.capability & (1 << bit) != 0)
+            .map(|bit| 1 << (bit + 16))
+            .collect()
+    }
+
+    /// Current size in bytes, or None if the encoded size does not fit in 64 bits.
+    pub fn current_size(&self) -> Option<u64> {
+        1u64.checked_shl(20 + ((self.control >> 8) & 0x3f))
+    }
+
+    /// Returns the control register value that resizes the BAR to `size` bytes, or None if
+    /// `size` is not supported. The BAR must be disabled while its size is changed.
+    pub fn resize_control(&self, size: u64) -> Option<u32> {
+        if !self.supported_sizes().contains(&size) {
+            return None;
+        }
+
+        let encoded = size.trailing_zeros() - 20;
+        Some(self.control & !(0x3f << 8) | encoded << 8)
+    }
+}
+
+impl Display for ResizableBarEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let supported: Vec<_> = self
+            .supported_sizes()
+            .into_iter()
+            .map(size_string)
+            .collect();
+
+        write!(
+            f,
+            "BAR {}: current size: {}, supported: {}",
+            self.index(),
+            self.current_size().map_or("<?>".to_string(), size_string),
+            supported.join(" ")
+        )
+    }
+}
+
+pub struct ResizableBarCapability {
+    access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    virtual_function: bool,
+    entries: Vec<ResizableBarEntry>,
+}
+
+impl ResizableBarCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<ResizableBarCapability> {
+        let first = BinaryParser::le32(&access.read(offset as u64 + 8, 4)?, 0..4)?;
+        let count = ((first >> 5) & 0x7).clamp(1, 6) as u16;
+
+        let mut entries = vec![];
+        for i in 0..count {
+            let entry_offset = offset + 4 + i * 8;
+            let raw = access.read(entry_offset.into(), 8)?;
+            entries.push(ResizableBarEntry {
+                offset: entry_offset,
+                capability: BinaryParser::le32(&raw, 0..4)?,
+                control: BinaryParser::le32(&raw, 4..8)?,
+            });
+        }
+
+        Ok(ResizableBarCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            access,
+            offset,
+            virtual_function: false,
+            entries,
+        })
+    }
+
+    /// Creates the VF Resizable BAR capability, whose entries refer to the SR-IOV VF BARs.
+    pub fn new_virtual(access: Rc<Box<dyn Access>>, offset: u16) -> Result<ResizableBarCapability> {
+        Ok(ResizableBarCapability {
+            virtual_function: true,
+            ..Self::new(access, offset)?
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<ResizableBarCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_extended(0x0015)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn entries(&self) -> &[ResizableBarEntry] {
+        &self.entries
+    }
+
+    pub fn entry(&self, index: u8) -> Option<&ResizableBarEntry> {
+        self.entries.iter().find(|entry| entry.index() == index)
+    }
+
+    pub fn is_virtual_function(&self) -> bool {
+        self.virtual_function
+    }
+
+    /// Returns the BAR, or the VF BAR, described by `entry`.
+    pub fn bar(&self, entry: &ResizableBarEntry) -> Result<Option<BAR>> {
+        if !self.virtual_function {
+            return Header::new(&self.access.read(0, 0x40)?)?.bar(entry.index());
+        }
+
+        match CapabilityFactory::lookup(Rc::clone(&self.access)).find_extended(0x0010)? {
+            Some(offset) => Ok(BAR::at(
+                SriovCapability::new(Rc::clone(&self.access), offset)?.vf_bars()?,
+                entry.index(),
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Capability for ResizableBarCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = match self.virtual_function {
+            false => "Physical Resizable BAR\n".to_string(),
+            true => "VF Resizable BAR\n".to_string(),
+        };
+
+        if verbosity >= 2 {
+            for entry in &self.entries {
+                text += &format!("\t\t{}\n", entry);
+                if let Some(bar) = self.bar(entry)? {
+                    text += &format!("\t\t\t{}\n", bar);
+                }
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for ResizableBarCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+fn size_string(size: u64) -> String {
+    match size.trailing_zeros() {
+        40.. => format!("{}TB", size >> 40),
+        30.. => format!("{}GB", size >> 30),
+        _ => format!("{}MB", size >> 20),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_entries() {
+        let mut dump = vec![0; 0x1000];
+        // 64-bit prefetchable BAR0 and 32-bit BAR2
+        dump[0x10..0x14].copy_from_slice(&0xc000_000cu32.to_le_bytes());
+        dump[0x18..0x1c].copy_from_slice(&0xa000_0000u32.to_le_bytes());
+        dump[0x200..0x204].copy_from_slice(&0x0001_0015u32.to_le_bytes());
+        // BAR 0 supports 256MB..16GB and is 256MB, BAR 2 supports 1MB..2MB and is 1MB
+        dump[0x204..0x208].copy_from_slice(&0x0007_f000u32.to_le_bytes());
+        dump[0x208..0x20c].copy_from_slice(&0x0000_0840u32.to_le_bytes());
+        dump[0x20c..0x210].copy_from_slice(&0x0000_0030u32.to_le_bytes());
+        dump[0x210..0x214].copy_from_slice(&0x0000_0002u32.to_le_bytes());
+
+        let cap =
+            ResizableBarCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x200).unwrap();
+
+        let entry = cap.entry(0).unwrap();
+        assert_eq!(entry.current_size(), Some(256 << 20));
+        assert_eq!(entry.supported_sizes().len(), 7);
+        assert_eq!(entry.control_offset(), 0x208);
+        assert_eq!(entry.resize_control(1 << 30), Some(0x0000_0a40));
+        assert_eq!(entry.resize_control(1 << 20), None);
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Physical Resizable BAR\n\
+             \t\tBAR 0: current size: 256MB, supported: 256MB 512MB 1GB 2GB 4GB 8GB 16GB\n\
+             \t\t\tMemory at c0000000 (64-bit, prefetchable)\n\
+             \t\tBAR 2: current size: 1MB, supported: 1MB 2MB\n\
+             \t\t\tMemory at a0000000 (32-bit, non-prefetchable)"
+        );
+    }
+
+    #[test]
+    fn test_reserved_size() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x200..0x204].copy_from_slice(&0x0001_0015u32.to_le_bytes());
+        dump[0x204..0x208].copy_from_slice(&0x0000_0010u32.to_le_bytes());
+        // BAR Size 0x3f does not fit in 64 bits
+        dump[0x208..0x20c].copy_from_slice(&0x0000_3f20u32.to_le_bytes());
+
+        let cap =
+            ResizableBarCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x200).unwrap();
+
+        assert_eq!(cap.entry(0).unwrap().current_size(), None);
+        assert!(cap
+            .cap_string(2)
+            .unwrap()
+            .contains("BAR 0: current size: <?>, supported: 1MB"));
+    }
+}
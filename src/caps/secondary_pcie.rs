@@ -0,0 +1,207 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::pci_express::{transmitter_preset, PciExpressCapability};
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+/// Equalization presets of one lane, as programmed in a Lane Equalization Control register.
+/// Receiver preset hints only exist for 8GT/s.
+pub struct LaneEqualization {
+    pub downstream_tx_preset: u8,
+    pub upstream_tx_preset: u8,
+    pub downstream_rx_hint: Option<u8>,
+    pub upstream_rx_hint: Option<u8>,
+}
+
+impl LaneEqualization {
+    pub fn from_8gt(register: u16) -> LaneEqualization {
+        LaneEqualization {
+            downstream_tx_preset: (register & 0xf) as u8,
+            upstream_tx_preset: ((register >> 8) & 0xf) as u8,
+            downstream_rx_hint: Some(((register >> 4) & 0x7) as u8),
+            upstream_rx_hint: Some(((register >> 12) & 0x7) as u8),
+        }
+    }
+
+    pub fn from_16gt(register: u8) -> LaneEqualization {
+        LaneEqualization {
+            downstream_tx_preset: register & 0xf,
+            upstream_tx_preset: register >> 4,
+            downstream_rx_hint: None,
+            upstream_rx_hint: None,
+        }
+    }
+
+    pub fn to_string(&self, verbosity: u8) -> String {
+        let mut text = format!(
+            "DsTxPreset=P{} UsTxPreset=P{}",
+            self.downstream_tx_preset, self.upstream_tx_preset
+        );
+
+        if let (Some(downstream), Some(upstream)) = (self.downstream_rx_hint, self.upstream_rx_hint)
+        {
+            text += &format!(" DsRxHint={} UsRxHint={}", downstream, upstream);
+        }
+
+        if verbosity >= 3 {
+            text += &format!(
+                "\n\t\t\tDsTx: {}\n\t\t\tUsTx: {}",
+                transmitter_preset(self.downstream_tx_preset),
+                transmitter_preset(self.upstream_tx_preset)
+            );
+        }
+
+        text
+    }
+}
+
+impl Display for LaneEqualization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_string(0))
+    }
+}
+
+/// Number of lanes with equalization registers, i.e. the maximum link width of the function.
+pub fn lanes(access: &Rc<Box<dyn Access>>) -> Result<usize> {
+    match PciExpressCapability::find(access)? {
+        Some(express) => Ok((express.max_link_width()? as usize).min(32)),
+        None => Ok(0),
+    }
+}
+
+pub struct SecondaryPciExpressCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+
+    link_control3: u32,
+    lane_error_status: u32,
+    lane_equalization: Vec<LaneEqualization>,
+}
+
+impl SecondaryPciExpressCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<SecondaryPciExpressCapability> {
+        let lanes = lanes(&access)?;
+        let raw = access.read(offset as u64 + 4, 8 + lanes * 2)?;
+
+        let mut lane_equalization = vec![];
+        for lane in 0..lanes {
+            let start = 8 + lane * 2;
+            lane_equalization.push(LaneEqualization::from_8gt(BinaryParser::le16(
+                &raw,
+                start..start + 2,
+            )?));
+        }
+
+        Ok(SecondaryPciExpressCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            link_control3: BinaryParser::le32(&raw, 0..4)?,
+            lane_error_status: BinaryParser::le32(&raw, 4..8)?,
+            lane_equalization,
+        })
+    }
+
+    pub fn link_control3(&self) -> u32 {
+        self.link_control3
+    }
+
+    pub fn lane_error_status(&self) -> u32 {
+        self.lane_error_status
+    }
+
+    pub fn lanes_in_error(&self) -> Vec<u8> {
+        (0..32)
+            .filter(|lane| self.lane_error_status & (1 << lane) != 0)
+            .collect()
+    }
+
+    pub fn lane_equalization(&self) -> &[LaneEqualization] {
+        &self.lane_equalization
+    }
+}
+
+impl Capability for SecondaryPciExpressCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Secondary PCI Express\n".to_string();
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tLnkCtl3: {}\n",
+                Flags::new(
+                    self.link_control3,
+                    &[("LnkEquIntrruptEn", 1), ("PerformEqu", 0)]
+                )
+            );
+
+            let lanes: Vec<_> = self
+                .lanes_in_error()
+                .iter()
+                .map(|lane| lane.to_string())
+                .collect();
+            text += &match lanes.is_empty() {
+                true => "\t\tLaneErrStat: 0\n".to_string(),
+                false => format!("\t\tLaneErrStat: LaneErr at lane: {}\n", lanes.join(" ")),
+            };
+
+            for (lane, equalization) in self.lane_equalization.iter().enumerate() {
+                text += &format!("\t\tLane {}: {}\n", lane, equalization.to_string(verbosity));
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for SecondaryPciExpressCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_lane_equalization() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x06] = 0x10;
+        dump[0x34] = 0x40;
+        // PCI Express endpoint with a x2 link
+        dump[0x40..0x44].copy_from_slice(&[0x10, 0x00, 0x02, 0x00]);
+        dump[0x4c..0x50].copy_from_slice(&0x0000_0024u32.to_le_bytes());
+        dump[0x300..0x304].copy_from_slice(&0x0001_0019u32.to_le_bytes());
+        dump[0x308..0x30c].copy_from_slice(&0x0000_0002u32.to_le_bytes());
+        dump[0x30c..0x30e].copy_from_slice(&0x7f24u16.to_le_bytes());
+        dump[0x30e..0x310].copy_from_slice(&0x2704u16.to_le_bytes());
+
+        let cap =
+            SecondaryPciExpressCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x300)
+                .unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Secondary PCI Express\n\
+             \t\tLnkCtl3: LnkEquIntrruptEn- PerformEqu-\n\
+             \t\tLaneErrStat: LaneErr at lane: 1\n\
+             \t\tLane 0: DsTxPreset=P4 UsTxPreset=P15 DsRxHint=2 UsRxHint=7\n\
+             \t\tLane 1: DsTxPreset=P4 UsTxPreset=P7 DsRxHint=0 UsRxHint=2"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flag;
+
+pub struct SlotIdCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    expansion_slot: u8,
+    chassis: u8,
+}
+
+impl SlotIdCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<SlotIdCapability> {
+        let raw = access.read(offset as u64 + 2, 2)?;
+
+        Ok(SlotIdCapability {
+            _access: access,
+            offset,
+            expansion_slot: BinaryParser::le8(&raw, 0..1)?,
+            chassis: BinaryParser::le8(&raw, 1..2)?,
+        })
+    }
+
+    pub fn slots(&self) -> u8 {
+        self.expansion_slot & 0x1f
+    }
+
+    pub fn first_in_chassis(&self) -> bool {
+        self.expansion_slot & (1 << 5) != 0
+    }
+
+    pub fn chassis(&self) -> u8 {
+        self.chassis
+    }
+}
+
+impl Capability for SlotIdCapability {
+    fn cap_string(&self, _verbosity: u8) -> Result<String> {
+        Ok(format!(
+            "Slot ID: {} slots, {}, chassis {:0>2x}",
+            self.slots(),
+            Flag::new("First", self.first_in_chassis()),
+            self.chassis
+        ))
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for SlotIdCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
@@ -0,0 +1,260 @@
+use crate::access::Access;
+use crate::bar::BAR;
+use crate::bdf::BusDeviceFunction;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::{Error, Result};
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flags;
+
+pub struct SriovCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    raw: Vec<u8>,
+}
+
+impl SriovCapability {
+    const LENGTH: usize = 0x40;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<SriovCapability> {
+        Ok(SriovCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            raw: access.read(offset.into(), Self::LENGTH)?,
+            _access: access,
+            offset,
+        })
+    }
+
+    pub fn capabilities(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x04..0x08)
+    }
+
+    pub fn control(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x08..0x0a)
+    }
+
+    pub fn status(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x0a..0x0c)
+    }
+
+    pub fn initial_vfs(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x0c..0x0e)
+    }
+
+    pub fn total_vfs(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x0e..0x10)
+    }
+
+    pub fn num_vfs(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x10..0x12)
+    }
+
+    pub fn function_dependency_link(&self) -> Result<u8> {
+        BinaryParser::le8(&self.raw, 0x12..0x13)
+    }
+
+    pub fn first_vf_offset(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x14..0x16)
+    }
+
+    pub fn vf_stride(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x16..0x18)
+    }
+
+    pub fn vf_device_id(&self) -> Result<u16> {
+        BinaryParser::le16(&self.raw, 0x1a..0x1c)
+    }
+
+    pub fn supported_page_sizes(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x1c..0x20)
+    }
+
+    pub fn system_page_size(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x20..0x24)
+    }
+
+    pub fn vf_bars(&self) -> Result<Vec<BAR>> {
+        let range = 0x24..0x3c;
+        Ok(BAR::new(
+            self.raw
+                .get(range.clone())
+                .ok_or(Error::slice_parse_error(&self.raw, &range))?,
+        ))
+    }
+
+    pub fn vf_migration_state(&self) -> Result<u32> {
+        BinaryParser::le32(&self.raw, 0x3c..0x40)
+    }
+
+    /// Returns the addresses of the currently enabled VFs of the physical function `pf`, named
+    /// with conventional device and function numbers.
+    pub fn virtual_functions(&self, pf: &BusDeviceFunction) -> Result<Vec<BusDeviceFunction>> {
+        if self.control()? & 0x1 == 0 {
+            return Ok(vec![]);
+        }
+
+        let routing_id = pf.routing_id().ok_or(Error::invalid_bdf(&pf.to_string()))?;
+        let domain = pf.domain().unwrap_or_default();
+
+        let first = routing_id as u32 + self.first_vf_offset()? as u32;
+        let stride = self.vf_stride()? as u32;
+
+        Ok((0..self.num_vfs()? as u32)
+            .map(|vf| first + vf * stride)
+            .take_while(|routing_id| *routing_id <= u16::MAX.into())
+            .map(|routing_id| BusDeviceFunction::from_routing_id(domain, routing_id as u16))
+            .collect())
+    }
+
+    fn regions_string(&self) -> Result<String> {
+        let mut text = String::new();
+        let mut index = 0;
+
+        for bar in self.vf_bars()? {
+            if bar.is_allocated() {
+                text += &format!("\t\tRegion {}: {}\n", index, bar);
+            }
+            index += bar.registers();
+        }
+
+        Ok(text)
+    }
+}
+
+impl Capability for SriovCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Single Root I/O Virtualization (SR-IOV)\n".to_string();
+
+        if verbosity >= 2 {
+            let cap = self.capabilities()?;
+            text += &format!(
+                "\t\tIOVCap:\t{} IntMsgNum {}\n",
+                Flags::new(cap, &[("Migration", 0), ("10BitTagReq", 2)]),
+                cap >> 21
+            );
+            text += &format!(
+                "\t\tIOVCtl:\t{}\n",
+                Flags::new(
+                    self.control()?.into(),
+                    &[
+                        ("Enable", 0),
+                        ("Migration", 1),
+                        ("Interrupt", 2),
+                        ("MSE", 3),
+                        ("ARIHierarchy", 4),
+                        ("10BitTagReq", 5)
+                    ]
+                )
+            );
+            text += &format!(
+                "\t\tIOVSta:\t{}\n",
+                Flags::new(self.status()?.into(), &[("Migration", 0)])
+            );
+            text += &format!(
+                "\t\tInitial VFs: {}, Total VFs: {}, Number of VFs: {}, Function Dependency Link: {:0>2x}\n",
+                self.initial_vfs()?,
+                self.total_vfs()?,
+                self.num_vfs()?,
+                self.function_dependency_link()?
+            );
+            text += &format!(
+                "\t\tVF offset: {}, stride: {}, Device ID: {:0>4x}\n",
+                self.first_vf_offset()?,
+                self.vf_stride()?,
+                self.vf_device_id()?
+            );
+            text += &format!(
+                "\t\tSupported Page Size: {:0>8x}, System Page Size: {:0>8x}\n",
+                self.supported_page_sizes()?,
+                self.system_page_size()?
+            );
+            text += &self.regions_string()?;
+
+            let migration = self.vf_migration_state()?;
+            text += &format!(
+                "\t\tVF Migration: offset: {:0>8x}, BIR: {}\n",
+                migration & !0x7,
+                migration & 0x7
+            );
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for SriovCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_virtual_functions() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x160..0x164].copy_from_slice(&0x0001_0010u32.to_le_bytes());
+        // VF Enable, NumVFs 3, First VF Offset 0x80, VF Stride 2
+        dump[0x168..0x16a].copy_from_slice(&0x0001u16.to_le_bytes());
+        dump[0x170..0x172].copy_from_slice(&3u16.to_le_bytes());
+        dump[0x174..0x176].copy_from_slice(&0x80u16.to_le_bytes());
+        dump[0x176..0x178].copy_from_slice(&2u16.to_le_bytes());
+        // VF BAR0 is 64-bit prefetchable, VF BAR3 is 64-bit non-prefetchable
+        dump[0x184..0x188].copy_from_slice(&0xd000_000cu32.to_le_bytes());
+        dump[0x190..0x194].copy_from_slice(&0xd010_0004u32.to_le_bytes());
+
+        let cap = SriovCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x160).unwrap();
+
+        let vfs: Vec<_> = cap
+            .virtual_functions(&BusDeviceFunction::from_str("0000:3b:00.1").unwrap())
+            .unwrap()
+            .iter()
+            .map(|bdf| bdf.canonical_bdf_string())
+            .collect();
+        assert_eq!(vfs, ["0000:3b:10.1", "0000:3b:10.3", "0000:3b:10.5"]);
+
+        dump[0x168..0x16a].copy_from_slice(&0x0000u16.to_le_bytes());
+        let disabled =
+            SriovCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x160).unwrap();
+        assert!(disabled
+            .virtual_functions(&BusDeviceFunction::from_str("0000:3b:00.1").unwrap())
+            .unwrap()
+            .is_empty());
+
+        let text = cap.cap_string(2).unwrap();
+        assert!(text.contains("\t\tRegion 0: Memory at d0000000 (64-bit, prefetchable)\n"));
+        assert!(text.contains("\t\tRegion 3: Memory at d0100000 (64-bit, non-prefetchable)\n"));
+    }
+
+    #[test]
+    fn test_trailing_64bit_vf_bar() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x160..0x164].copy_from_slice(&0x0001_0010u32.to_le_bytes());
+        // VF BAR5 claims to be the lower half of a 64-bit BAR
+        dump[0x198..0x19c].copy_from_slice(&0xd020_0004u32.to_le_bytes());
+
+        let cap = SriovCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x160).unwrap();
+
+        assert_eq!(cap.vf_bars().unwrap().len(), 6);
+        assert!(cap
+            .cap_string(2)
+            .unwrap()
+            .contains("\t\tRegion 5: Memory at d0200000 (32-bit, non-prefetchable)"));
+    }
+}
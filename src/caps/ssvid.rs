@@ -0,0 +1,63 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::header::{CommonHeader, Header};
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+/// The Subsystem Vendor ID capability, which bridges use in place of the Type 0 header fields.
+pub struct BridgeSubsystemCapability {
+    access: Rc<Box<dyn Access>>,
+    offset: u8,
+
+    subsystem_vendor_id: u16,
+    subsystem_id: u16,
+}
+
+impl BridgeSubsystemCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<BridgeSubsystemCapability> {
+        let raw = access.read(offset as u64 + 4, 4)?;
+
+        Ok(BridgeSubsystemCapability {
+            access,
+            offset,
+            subsystem_vendor_id: BinaryParser::le16(&raw, 0..2)?,
+            subsystem_id: BinaryParser::le16(&raw, 2..4)?,
+        })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<BridgeSubsystemCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_trad(0x0d)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn subsystem_vendor_id(&self) -> u16 {
+        self.subsystem_vendor_id
+    }
+
+    pub fn subsystem_id(&self) -> u16 {
+        self.subsystem_id
+    }
+}
+
+impl Capability for BridgeSubsystemCapability {
+    fn cap_string(&self, _verbosity: u8) -> Result<String> {
+        Header::new(&self.access.read(0, 0x40)?)?
+            .subsystem_string_for(self.subsystem_vendor_id, self.subsystem_id)
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for BridgeSubsystemCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
@@ -68,6 +68,13 @@ impl UnknownExtendedCapability {
         )
     }
 
+    pub fn version(access: &Rc<Box<dyn Access>>, offset: u16) -> Result<u8> {
+        Ok((binary_parser::BinaryParser::le16(
+            &access.read(offset as u64 + 2, 2)?,
+            Range { start: 0, end: 2 },
+        )? & 0xf) as u8)
+    }
+
     pub fn next(access: &Rc<Box<dyn Access>>, offset: u16) -> Result<u16> {
         Ok(binary_parser::BinaryParser::le16(
             &access.read(offset as u64 + 2, 2)?,
@@ -85,6 +92,10 @@ impl Capability for UnknownExtendedCapability {
     fn offset(&self) -> Result<u64> {
         Ok(self.offset.into())
     }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(Self::version(&self.access, self.offset)?))
+    }
 }
 
 impl Display for UnknownExtendedCapability {
@@ -0,0 +1,398 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::Flag;
+use super::EXTENDED_CONFIG_SPACE_END;
+
+const ARBITRATION: [&str; 8] = [
+    "Fixed", "WRR32", "WRR64", "WRR128", "??4", "??5", "??6", "??7",
+];
+const RESOURCE_ARBITRATION: [&str; 8] = [
+    "Fixed", "WRR32", "WRR64", "WRR128", "TWRR128", "WRR256", "??6", "??7",
+];
+
+// Joins the arbitration schemes in the low byte of `register`, omitting reserved ones unless
+// they are advertised.
+fn arbitration_string(register: u32, names: &[&'static str; 8]) -> String {
+    let schemes: Vec<_> = names
+        .iter()
+        .enumerate()
+        .filter(|(i, name)| !name.starts_with('?') || register & (1 << i) != 0)
+        .map(|(i, name)| Flag::new(name, register & (1 << i) != 0).to_string())
+        .collect();
+
+    schemes.join(" ")
+}
+
+/// Number of phases of the arbitration table used by arbitration scheme `select`.
+fn table_phases(select: u32) -> usize {
+    match select {
+        1 => 32,
+        2 => 64,
+        3 | 4 => 128,
+        5 => 256,
+        _ => 0,
+    }
+}
+
+pub struct VirtualChannelResource {
+    pub capabilities: u32,
+    pub control: u32,
+    pub status: u16,
+    pub arbitration_table: Vec<u8>,
+}
+
+impl VirtualChannelResource {
+    pub fn enabled(&self) -> bool {
+        self.control & (1 << 31) != 0
+    }
+
+    pub fn id(&self) -> u8 {
+        ((self.control >> 24) & 0x7) as u8
+    }
+
+    /// Traffic classes mapped to this VC, one bit per TC.
+    pub fn tc_vc_map(&self) -> u8 {
+        self.control as u8
+    }
+
+    pub fn negotiation_pending(&self) -> bool {
+        self.status & (1 << 1) != 0
+    }
+
+    fn table_offset(&self) -> u32 {
+        self.capabilities >> 24
+    }
+}
+
+/// The Virtual Channel and Multi-Function Virtual Channel extended capabilities.
+pub struct VirtualChannelCapability {
+    _access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+    multi_function: bool,
+
+    capabilities1: u32,
+    capabilities2: u32,
+    control: u16,
+    status: u16,
+    arbitration_table: Vec<u8>,
+    resources: Vec<VirtualChannelResource>,
+}
+
+impl VirtualChannelCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<VirtualChannelCapability> {
+        let raw = access.read(offset as u64 + 4, 0xc)?;
+        let capabilities1 = BinaryParser::le32(&raw, 0..4)?;
+        let capabilities2 = BinaryParser::le32(&raw, 4..8)?;
+        let control = BinaryParser::le16(&raw, 8..10)?;
+        let entry_bits = 1 << ((capabilities1 >> 10) & 0x3);
+
+        let mut resources = vec![];
+        for i in 0..=(capabilities1 & 0x7) as u16 {
+            let resource = offset as u32 + 0x10 + 12 * i as u32;
+            if resource + 12 > EXTENDED_CONFIG_SPACE_END.into() {
+                break;
+            }
+            let raw = access.read(resource.into(), 12)?;
+            let capabilities = BinaryParser::le32(&raw, 0..4)?;
+            let control = BinaryParser::le32(&raw, 4..8)?;
+
+            let arbitration_table = match capabilities >> 24 {
+                0 => vec![],
+                table => Self::table(
+                    &access,
+                    offset as u32 + 16 * table,
+                    table_phases((control >> 17) & 0x7),
+                    entry_bits,
+                )?,
+            };
+
+            resources.push(VirtualChannelResource {
+                capabilities,
+                control,
+                status: BinaryParser::le16(&raw, 10..12)?,
+                arbitration_table,
+            });
+        }
+
+        let arbitration_table = match capabilities2 >> 24 {
+            0 => vec![],
+            table => Self::table(
+                &access,
+                offset as u32 + 16 * table,
+                table_phases(((control >> 1) & 0x7).into()),
+                4,
+            )?,
+        };
+
+        Ok(VirtualChannelCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            _access: access,
+            offset,
+            multi_function: false,
+            capabilities1,
+            capabilities2,
+            control,
+            status: BinaryParser::le16(&raw, 10..12)?,
+            arbitration_table,
+            resources,
+        })
+    }
+
+    pub fn new_multi_function(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+    ) -> Result<VirtualChannelCapability> {
+        Ok(VirtualChannelCapability {
+            multi_function: true,
+            ..Self::new(access, offset)?
+        })
+    }
+
+    // Reads the `phases` entries of `entry_bits` each of an arbitration table, lowest phase first.
+    // A table that does not fit in config space is left empty.
+    fn table(
+        access: &Rc<Box<dyn Access>>,
+        offset: u32,
+        phases: usize,
+        entry_bits: usize,
+    ) -> Result<Vec<u8>> {
+        let length = (phases * entry_bits).div_ceil(8);
+        if offset as usize + length > EXTENDED_CONFIG_SPACE_END.into() {
+            return Ok(vec![]);
+        }
+
+        let raw = access.read(offset.into(), length)?;
+        let mask = ((1u16 << entry_bits) - 1) as u8;
+
+        (0..phases)
+            .map(|phase| {
+                let bit = phase * entry_bits;
+                let byte = BinaryParser::le8(&raw, bit / 8..bit / 8 + 1)?;
+                Ok((byte >> (bit % 8)) & mask)
+            })
+            .collect()
+    }
+
+    pub fn capabilities1(&self) -> u32 {
+        self.capabilities1
+    }
+
+    pub fn capabilities2(&self) -> u32 {
+        self.capabilities2
+    }
+
+    pub fn control(&self) -> u16 {
+        self.control
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn arbitration_table(&self) -> &[u8] {
+        &self.arbitration_table
+    }
+
+    pub fn resources(&self) -> &[VirtualChannelResource] {
+        &self.resources
+    }
+
+    fn table_string(table: &[u8], indent: &str) -> String {
+        table
+            .chunks(32)
+            .map(|phases| {
+                let entries: Vec<_> = phases.iter().map(|e| format!("{:x}", e)).collect();
+                format!("{}{}\n", indent, entries.join(" "))
+            })
+            .collect()
+    }
+
+    fn resource_string(
+        &self,
+        i: usize,
+        resource: &VirtualChannelResource,
+        verbosity: u8,
+    ) -> String {
+        let mut text = format!(
+            "\t\tVC{}:\tCaps:\tPATOffset={:0>2x} MaxTimeSlots={} {}\n",
+            i,
+            resource.table_offset(),
+            ((resource.capabilities >> 16) & 0x3f) + 1,
+            Flag::new("RejSnoopTrans", resource.capabilities & (1 << 15) != 0)
+        );
+        text += &format!(
+            "\t\t\tArb:\t{}\n",
+            arbitration_string(resource.capabilities, &RESOURCE_ARBITRATION)
+        );
+        text += &format!(
+            "\t\t\tCtrl:\t{} ID={} ArbSelect={} TC/VC={:0>2x}\n",
+            Flag::new("Enable", resource.enabled()),
+            resource.id(),
+            RESOURCE_ARBITRATION[((resource.control >> 17) & 0x7) as usize],
+            resource.tc_vc_map()
+        );
+        text += &format!(
+            "\t\t\tStatus:\t{} {}\n",
+            Flag::new("NegoPending", resource.negotiation_pending()),
+            Flag::new("InProgress", resource.status & 0x1 != 0)
+        );
+
+        if resource.table_offset() != 0 {
+            let name = match self.multi_function {
+                false => "Port",
+                true => "Function",
+            };
+            if verbosity >= 3 && !resource.arbitration_table.is_empty() {
+                text += &format!("\t\t\t{} Arbitration Table:\n", name);
+                text += &Self::table_string(&resource.arbitration_table, "\t\t\t\t");
+            } else {
+                text += &format!("\t\t\t{} Arbitration Table <?>\n", name);
+            }
+        }
+
+        text
+    }
+}
+
+impl Capability for VirtualChannelCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = match self.multi_function {
+            false => "Virtual Channel\n".to_string(),
+            true => "Multi-Function Virtual Channel\n".to_string(),
+        };
+
+        if verbosity >= 2 {
+            text += &format!(
+                "\t\tCaps:\tLPEVC={} RefClk={} PATEntryBits={}\n",
+                (self.capabilities1 >> 4) & 0x7,
+                match (self.capabilities1 >> 8) & 0x3 {
+                    0 => "100ns".to_string(),
+                    clock => format!("??{}", clock),
+                },
+                1 << ((self.capabilities1 >> 10) & 0x3)
+            );
+            text += &format!(
+                "\t\tArb:\t{}\n",
+                arbitration_string(self.capabilities2, &ARBITRATION)
+            );
+            text += &format!(
+                "\t\tCtrl:\tArbSelect={}\n",
+                ARBITRATION[((self.control >> 1) & 0x7) as usize]
+            );
+            text += &format!(
+                "\t\tStatus:\t{}\n",
+                Flag::new("InProgress", self.status & 0x1 != 0)
+            );
+
+            let table = self.capabilities2 >> 24;
+            if table != 0 {
+                text += &format!(
+                    "\t\tPort Arbitration Table [{:x}] <?>\n",
+                    self.offset as u32 + 16 * table
+                );
+                if verbosity >= 3 {
+                    text += &Self::table_string(&self.arbitration_table, "\t\t\t");
+                }
+            }
+
+            for (i, resource) in self.resources.iter().enumerate() {
+                text += &self.resource_string(i, resource, verbosity);
+            }
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for VirtualChannelCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    #[test]
+    fn test_tables_past_end_of_config_space() {
+        let mut dump = vec![0; 0x2000];
+        dump[0xfe0..0xfe4].copy_from_slice(&0x0001_0002u32.to_le_bytes());
+        // Seven extended VCs and a VC Arbitration Table at offset 0xff0 from the capability
+        dump[0xfe4..0xfe8].copy_from_slice(&0x0000_0007u32.to_le_bytes());
+        dump[0xfe8..0xfec].copy_from_slice(&0xff00_0000u32.to_le_bytes());
+        dump[0xfec..0xfee].copy_from_slice(&0x0002u16.to_le_bytes());
+        // VC0 with a WRR32 Port Arbitration Table at offset 0xff0
+        dump[0xff0..0xff4].copy_from_slice(&0xff00_0002u32.to_le_bytes());
+        dump[0xff4..0xff8].copy_from_slice(&0x0002_0001u32.to_le_bytes());
+
+        let cap = VirtualChannelCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0xfe0)
+            .unwrap();
+
+        assert_eq!(cap.resources().len(), 1);
+        assert!(cap.resources()[0].arbitration_table.is_empty());
+        assert!(cap.arbitration_table.is_empty());
+        assert!(cap.cap_string(2).is_ok());
+    }
+
+    #[test]
+    fn test_virtual_channel() {
+        let mut dump = vec![0; 0x1000];
+        dump[0x100..0x104].copy_from_slice(&0x0001_0002u32.to_le_bytes());
+        // One extended VC
+        dump[0x104..0x108].copy_from_slice(&0x0000_0001u32.to_le_bytes());
+        // VC0 maps TC0-6 with WRR32 port arbitration, VC1 is enabled with TC7 and negotiation
+        // pending
+        dump[0x110..0x114].copy_from_slice(&0x0600_8003u32.to_le_bytes());
+        dump[0x114..0x118].copy_from_slice(&0x8002_007fu32.to_le_bytes());
+        dump[0x11c..0x120].copy_from_slice(&0x0000_0002u32.to_le_bytes());
+        dump[0x120..0x124].copy_from_slice(&0x8100_0080u32.to_le_bytes());
+        dump[0x126..0x128].copy_from_slice(&0x0002u16.to_le_bytes());
+        // Port Arbitration Table of VC0 with port 1 in phase 0
+        dump[0x160..0x164].copy_from_slice(&0x0000_0001u32.to_le_bytes());
+
+        let cap = VirtualChannelCapability::new(Rc::new(Box::new(DumpAccess::new(&dump))), 0x100)
+            .unwrap();
+
+        let resources = cap.resources();
+        assert_eq!(resources.len(), 2);
+        assert_eq!(resources[1].tc_vc_map(), 0x80);
+        assert!(resources[1].negotiation_pending());
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Virtual Channel\n\
+             \t\tCaps:\tLPEVC=0 RefClk=100ns PATEntryBits=1\n\
+             \t\tArb:\tFixed- WRR32- WRR64- WRR128-\n\
+             \t\tCtrl:\tArbSelect=Fixed\n\
+             \t\tStatus:\tInProgress-\n\
+             \t\tVC0:\tCaps:\tPATOffset=06 MaxTimeSlots=1 RejSnoopTrans+\n\
+             \t\t\tArb:\tFixed+ WRR32+ WRR64- WRR128- TWRR128- WRR256-\n\
+             \t\t\tCtrl:\tEnable+ ID=0 ArbSelect=WRR32 TC/VC=7f\n\
+             \t\t\tStatus:\tNegoPending- InProgress-\n\
+             \t\t\tPort Arbitration Table <?>\n\
+             \t\tVC1:\tCaps:\tPATOffset=00 MaxTimeSlots=1 RejSnoopTrans-\n\
+             \t\t\tArb:\tFixed- WRR32+ WRR64- WRR128- TWRR128- WRR256-\n\
+             \t\t\tCtrl:\tEnable+ ID=1 ArbSelect=Fixed TC/VC=80\n\
+             \t\t\tStatus:\tNegoPending+ InProgress-"
+        );
+        assert_eq!(resources[0].arbitration_table.len(), 32);
+        assert_eq!(resources[0].arbitration_table[..2], [1, 0]);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::unknown::UnknownExtendedCapability;
+use crate::error::Result;
+use pretty_hex::{config_hex, HexConfig};
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+use super::EXTENDED_CONFIG_SPACE_END;
+
+pub type VendorSpecificDecoder = fn(&VendorSpecificCapability, u8) -> Result<String>;
+pub type DesignatedVendorSpecificDecoder =
+    fn(&DesignatedVendorSpecificCapability, u8) -> Result<String>;
+
+pub struct VendorSpecificCapability {
+    access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+
+    id: u16,
+    revision: u8,
+    length: u16,
+    body: Vec<u8>,
+
+    name: Option<&'static str>,
+    decoder: Option<VendorSpecificDecoder>,
+}
+
+impl VendorSpecificCapability {
+    const HEADER_LENGTH: u16 = 0x8;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Result<VendorSpecificCapability> {
+        let header = BinaryParser::le32(&access.read(offset as u64 + 4, 4)?, 0..4)?;
+        let length = (header >> 20) as u16;
+        // Devices may report a length running past the end of config space
+        let body = access.read(
+            (offset + Self::HEADER_LENGTH).into(),
+            length
+                .min(EXTENDED_CONFIG_SPACE_END.saturating_sub(offset))
+                .saturating_sub(Self::HEADER_LENGTH)
+                .into(),
+        )?;
+
+        Ok(VendorSpecificCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            access,
+            offset,
+            id: (header & 0xffff) as u16,
+            revision: ((header >> 16) & 0xf) as u8,
+            length,
+            body,
+            name: None,
+            decoder: None,
+        })
+    }
+
+    /// Creates a VSEC whose body is rendered by `decoder` instead of a hex dump.
+    pub fn with_decoder(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+        name: &'static str,
+        decoder: VendorSpecificDecoder,
+    ) -> Result<VendorSpecificCapability> {
+        Ok(VendorSpecificCapability {
+            name: Some(name),
+            decoder: Some(decoder),
+            ..Self::new(access, offset)?
+        })
+    }
+
+    pub fn access(&self) -> &Rc<Box<dyn Access>> {
+        &self.access
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn revision(&self) -> u8 {
+        self.revision
+    }
+
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+impl Capability for VendorSpecificCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!(
+            "Vendor Specific Information: ID={:0>4x} Rev={} Len={:0>3x} {}\n",
+            self.id,
+            self.revision,
+            self.length,
+            self.name.unwrap_or("<?>")
+        );
+
+        if verbosity >= 2 {
+            text += &match self.decoder {
+                Some(decoder) => decoder(self, verbosity)?,
+                None => body_hex(&self.body),
+            };
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for VendorSpecificCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+pub struct DesignatedVendorSpecificCapability {
+    access: Rc<Box<dyn Access>>,
+    offset: u16,
+    version: u8,
+
+    vendor: u16,
+    revision: u8,
+    length: u16,
+    id: u16,
+    body: Vec<u8>,
+
+    name: Option<&'static str>,
+    decoder: Option<DesignatedVendorSpecificDecoder>,
+}
+
+impl DesignatedVendorSpecificCapability {
+    const HEADER_LENGTH: u16 = 0xa;
+
+    pub fn new(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+    ) -> Result<DesignatedVendorSpecificCapability> {
+        let header = access.read(offset as u64 + 4, 6)?;
+        let header1 = BinaryParser::le32(&header, 0..4)?;
+        let length = (header1 >> 20) as u16;
+        // Devices may report a length running past the end of config space
+        let body = access.read(
+            (offset + Self::HEADER_LENGTH).into(),
+            length
+                .min(EXTENDED_CONFIG_SPACE_END.saturating_sub(offset))
+                .saturating_sub(Self::HEADER_LENGTH)
+                .into(),
+        )?;
+
+        Ok(DesignatedVendorSpecificCapability {
+            version: UnknownExtendedCapability::version(&access, offset)?,
+            access,
+            offset,
+            vendor: (header1 & 0xffff) as u16,
+            revision: ((header1 >> 16) & 0xf) as u8,
+            length,
+            id: BinaryParser::le16(&header, 4..6)?,
+            body,
+            name: None,
+            decoder: None,
+        })
+    }
+
+    /// Creates a DVSEC whose body is rendered by `decoder` instead of a hex dump.
+    pub fn with_decoder(
+        access: Rc<Box<dyn Access>>,
+        offset: u16,
+        name: &'static str,
+        decoder: DesignatedVendorSpecificDecoder,
+    ) -> Result<DesignatedVendorSpecificCapability> {
+        Ok(DesignatedVendorSpecificCapability {
+            name: Some(name),
+            decoder: Some(decoder),
+            ..Self::new(access, offset)?
+        })
+    }
+
+    pub fn access(&self) -> &Rc<Box<dyn Access>> {
+        &self.access
+    }
+
+    pub fn vendor(&self) -> u16 {
+        self.vendor
+    }
+
+    pub fn revision(&self) -> u8 {
+        self.revision
+    }
+
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Registers following the DVSEC headers, i.e. starting at offset 0xa of the capability.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+impl Capability for DesignatedVendorSpecificCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = format!(
+            "Designated Vendor-Specific: Vendor={:0>4x} ID={:0>4x} Rev={} Len={}{}\n",
+            self.vendor,
+            self.id,
+            self.revision,
+            self.length,
+            match self.name {
+                Some(name) => format!(": {}", name),
+                None => " <?>".to_string(),
+            }
+        );
+
+        if verbosity >= 2 {
+            text += &match self.decoder {
+                Some(decoder) => decoder(self, verbosity)?,
+                None => body_hex(&self.body),
+            };
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+
+    fn version(&self) -> Result<Option<u8>> {
+        Ok(Some(self.version))
+    }
+}
+
+impl Display for DesignatedVendorSpecificCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
+
+fn body_hex(body: &[u8]) -> String {
+    if body.is_empty() {
+        return String::new();
+    }
+
+    let hex_config = HexConfig {
+        title: false,
+        width: 16,
+        group: 0,
+        ascii: false,
+        ..HexConfig::default()
+    };
+
+    config_hex(&body, hex_config)
+        .lines()
+        .map(|line| format!("\t\t{}\n", line))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::dump::DumpAccess;
+
+    fn telemetry(dvsec: &DesignatedVendorSpecificCapability, _verbosity: u8) -> Result<String> {
+        Ok(format!(
+            "\t\tSamples: {}\n",
+            BinaryParser::le16(dvsec.body(), 2..4)?
+        ))
+    }
+
+    fn dump() -> Rc<Box<dyn Access>> {
+        let mut dump = vec![0; 0x1000];
+        dump[0x100..0x104].copy_from_slice(&0x0001_0023u32.to_le_bytes());
+        dump[0x104..0x108].copy_from_slice(&0x0101_1234u32.to_le_bytes());
+        dump[0x108..0x10a].copy_from_slice(&0x0007u16.to_le_bytes());
+        dump[0x10c..0x10e].copy_from_slice(&0x002au16.to_le_bytes());
+
+        Rc::new(Box::new(DumpAccess::new(&dump)))
+    }
+
+    #[test]
+    fn test_length_past_end_of_config_space() {
+        let mut dump = vec![0; 0x2000];
+        dump[0xff0..0xff4].copy_from_slice(&0x0001_000bu32.to_le_bytes());
+        dump[0xff4..0xff8].copy_from_slice(&0xfff1_0042u32.to_le_bytes());
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(DumpAccess::new(&dump)));
+        let vsec = VendorSpecificCapability::new(Rc::clone(&access), 0xff0).unwrap();
+        assert_eq!(vsec.body().len(), 8);
+
+        let dvsec = DesignatedVendorSpecificCapability::new(access, 0xff0).unwrap();
+        assert_eq!(dvsec.body().len(), 6);
+    }
+
+    #[test]
+    fn test_hexdump_fallback() {
+        let cap = DesignatedVendorSpecificCapability::new(dump(), 0x100).unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Designated Vendor-Specific: Vendor=1234 ID=0007 Rev=1 Len=16 <?>\n\t\t0000:   00 00 2a 00 00 00"
+        );
+    }
+
+    #[test]
+    fn test_decoder() {
+        let cap =
+            DesignatedVendorSpecificCapability::with_decoder(dump(), 0x100, "Telemetry", telemetry)
+                .unwrap();
+
+        assert_eq!(
+            cap.cap_string(2).unwrap(),
+            "Designated Vendor-Specific: Vendor=1234 ID=0007 Rev=1 Len=16: Telemetry\n\t\tSamples: 42"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::access::Access;
+use crate::caps::CapabilityFactory;
+use crate::error::Result;
+use crate::vpd::{Vpd, VpdKeyword, VpdReader};
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::Capability;
+
+pub struct VitalProductDataCapability {
+    access: Rc<Box<dyn Access>>,
+    offset: u8,
+}
+
+impl VitalProductDataCapability {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> Result<VitalProductDataCapability> {
+        Ok(VitalProductDataCapability { access, offset })
+    }
+
+    pub fn find(access: &Rc<Box<dyn Access>>) -> Result<Option<VitalProductDataCapability>> {
+        match CapabilityFactory::lookup(Rc::clone(access)).find_trad(0x03)? {
+            Some(offset) => Ok(Some(Self::new(Rc::clone(access), offset)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn reader(&self) -> VpdReader {
+        VpdReader::new(Rc::clone(&self.access), self.offset)
+    }
+
+    pub fn vpd(&self) -> Result<Vpd> {
+        self.reader().vpd()
+    }
+
+    fn keyword_string(keyword: &VpdKeyword, checksum_valid: Option<bool>) -> String {
+        let value = match keyword.keyword.as_str() {
+            "RV" => format!(
+                "checksum {}, {} byte(s) reserved",
+                match checksum_valid {
+                    Some(true) => "good",
+                    _ => "bad",
+                },
+                keyword.data.len().saturating_sub(1)
+            ),
+            "RW" => format!("{} byte(s) free", keyword.data.len()),
+            "CP" => keyword
+                .data
+                .iter()
+                .map(|byte| format!("{:0>2x}", byte))
+                .collect::<Vec<_>>()
+                .join(" "),
+            _ => keyword.text(),
+        };
+
+        format!(
+            "\t\t\t[{}] {}: {}\n",
+            keyword.keyword,
+            keyword.name(),
+            value
+        )
+    }
+
+    fn vpd_string(vpd: &Vpd) -> String {
+        let mut text = String::new();
+
+        if let Some(identifier) = &vpd.identifier {
+            text += &format!("\t\tProduct Name: {}\n", identifier);
+        }
+
+        if !vpd.read_only.is_empty() {
+            text += "\t\tRead-only fields:\n";
+            for keyword in &vpd.read_only {
+                text += &Self::keyword_string(keyword, vpd.checksum_valid);
+            }
+        }
+
+        if !vpd.read_write.is_empty() {
+            text += "\t\tRead/write fields:\n";
+            for keyword in &vpd.read_write {
+                text += &Self::keyword_string(keyword, vpd.checksum_valid);
+            }
+        }
+
+        text + "\t\tEnd\n"
+    }
+}
+
+impl Capability for VitalProductDataCapability {
+    fn cap_string(&self, verbosity: u8) -> Result<String> {
+        let mut text = "Vital Product Data\n".to_string();
+
+        if verbosity >= 2 {
+            text += &match self.vpd() {
+                Ok(vpd) => Self::vpd_string(&vpd),
+                Err(_) => "\t\tNot readable\n".to_string(),
+            };
+        }
+
+        Ok(text.trim().to_string())
+    }
+
+    fn offset(&self) -> Result<u64> {
+        Ok(self.offset.into())
+    }
+}
+
+impl Display for VitalProductDataCapability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cap_string(0)?)
+    }
+}
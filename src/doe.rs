@@ -0,0 +1,322 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::doe::DataObjectExchangeCapability as Doe;
+use crate::error::{Error, Result};
+use std::fmt::Display;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const PCI_SIG_VENDOR_ID: u16 = 0x0001;
+pub const CXL_VENDOR_ID: u16 = 0x1e98;
+
+/// A data object protocol, identified by its vendor ID and data object type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataObjectProtocol {
+    pub vendor_id: u16,
+    pub data_object_type: u8,
+}
+
+impl DataObjectProtocol {
+    pub const DISCOVERY: DataObjectProtocol = DataObjectProtocol::new(PCI_SIG_VENDOR_ID, 0x00);
+    pub const CMA_SPDM: DataObjectProtocol = DataObjectProtocol::new(PCI_SIG_VENDOR_ID, 0x01);
+    pub const SECURED_CMA_SPDM: DataObjectProtocol =
+        DataObjectProtocol::new(PCI_SIG_VENDOR_ID, 0x02);
+    pub const CXL_COMPLIANCE: DataObjectProtocol = DataObjectProtocol::new(CXL_VENDOR_ID, 0x00);
+    pub const CDAT: DataObjectProtocol = DataObjectProtocol::new(CXL_VENDOR_ID, 0x02);
+
+    pub const fn new(vendor_id: u16, data_object_type: u8) -> DataObjectProtocol {
+        DataObjectProtocol {
+            vendor_id,
+            data_object_type,
+        }
+    }
+
+    fn header(&self) -> u32 {
+        self.vendor_id as u32 | (self.data_object_type as u32) << 16
+    }
+}
+
+impl Display for DataObjectProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::DISCOVERY => write!(f, "Discovery"),
+            Self::CMA_SPDM => write!(f, "CMA/SPDM"),
+            Self::SECURED_CMA_SPDM => write!(f, "Secured CMA/SPDM"),
+            Self::CXL_COMPLIANCE => write!(f, "CXL Compliance"),
+            Self::CDAT => write!(f, "CDAT"),
+            _ => write!(
+                f,
+                "Vendor {:0>4x} Type {:0>2x}",
+                self.vendor_id, self.data_object_type
+            ),
+        }
+    }
+}
+
+/// Exchanges data objects through the DOE mailbox at `offset`.
+pub struct Mailbox {
+    access: Rc<Box<dyn Access>>,
+    offset: u16,
+    timeout: Duration,
+}
+
+impl Mailbox {
+    // Data object length is counted in DWORDs and 0 stands for the maximum.
+    const MAX_LENGTH: usize = 1 << 18;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16) -> Mailbox {
+        Mailbox {
+            access,
+            offset,
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn read_register(&self, register: u16) -> Result<u32> {
+        BinaryParser::le32(&self.access.read((self.offset + register).into(), 4)?, 0..4)
+    }
+
+    fn write_register(&self, register: u16, value: u32) -> Result<()> {
+        self.access
+            .write((self.offset + register).into(), &value.to_le_bytes())?;
+        Ok(())
+    }
+
+    fn status(&self) -> Result<u32> {
+        self.read_register(Doe::STATUS)
+    }
+
+    fn wait_for(&self, done: impl Fn(u32) -> bool, what: &str) -> Result<u32> {
+        let start = Instant::now();
+
+        loop {
+            let status = self.status()?;
+            if done(status) {
+                return Ok(status);
+            }
+
+            if start.elapsed() > self.timeout {
+                return Err(Error::doe_error(&format!(
+                    "DOE mailbox at {:#x} timed out waiting for {}",
+                    self.offset, what
+                )));
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Aborts any exchange in progress and clears the error status.
+    pub fn abort(&self) -> Result<()> {
+        self.write_register(Doe::CONTROL, Doe::CONTROL_ABORT)?;
+        self.wait_for(
+            |status| status & (Doe::STATUS_BUSY | Doe::STATUS_ERROR) == 0,
+            "abort",
+        )?;
+        Ok(())
+    }
+
+    /// Sends a request data object and returns the payload of the response, without the data
+    /// object header.
+    pub fn exchange(&self, protocol: DataObjectProtocol, request: &[u32]) -> Result<Vec<u32>> {
+        if self.status()? & Doe::STATUS_ERROR != 0 {
+            self.abort()?;
+        }
+        self.wait_for(|status| status & Doe::STATUS_BUSY == 0, "the mailbox")?;
+
+        let length = request.len() + 2;
+        if length > Self::MAX_LENGTH {
+            return Err(Error::doe_error(&format!(
+                "Request of {} DWORDs does not fit a data object",
+                length
+            )));
+        }
+
+        self.write_register(Doe::WRITE_MAILBOX, protocol.header())?;
+        self.write_register(Doe::WRITE_MAILBOX, (length % Self::MAX_LENGTH) as u32)?;
+        for dword in request {
+            self.write_register(Doe::WRITE_MAILBOX, *dword)?;
+        }
+
+        let control = self.read_register(Doe::CONTROL)? & Doe::CONTROL_INTERRUPT_ENABLE;
+        self.write_register(Doe::CONTROL, control | Doe::CONTROL_GO)?;
+
+        let status = self.wait_for(
+            |status| status & (Doe::STATUS_DATA_OBJECT_READY | Doe::STATUS_ERROR) != 0,
+            "a response",
+        )?;
+        if status & Doe::STATUS_ERROR != 0 {
+            self.abort()?;
+            return Err(Error::doe_error(&format!(
+                "DOE mailbox at {:#x} reported an error for {}",
+                self.offset, protocol
+            )));
+        }
+
+        let header = self.read_dword()?;
+        let length = match self.read_dword()? as usize & (Self::MAX_LENGTH - 1) {
+            0 => Self::MAX_LENGTH,
+            length => length,
+        };
+
+        let mut response = vec![];
+        for _ in 2..length {
+            response.push(self.read_dword()?);
+        }
+
+        if header != protocol.header() {
+            return Err(Error::doe_error(&format!(
+                "DOE mailbox at {:#x} answered {} with header {:0>8x}",
+                self.offset, protocol, header
+            )));
+        }
+
+        Ok(response)
+    }
+
+    // Reads the next DWORD of the response, writing the read mailbox pops it.
+    fn read_dword(&self) -> Result<u32> {
+        let dword = self.read_register(Doe::READ_MAILBOX)?;
+        self.write_register(Doe::READ_MAILBOX, 0)?;
+        Ok(dword)
+    }
+
+    /// Walks the discovery protocol and returns every protocol the mailbox supports.
+    pub fn discover(&self) -> Result<Vec<DataObjectProtocol>> {
+        let mut protocols = vec![];
+        let mut index = 0;
+
+        loop {
+            let response = self.exchange(DataObjectProtocol::DISCOVERY, &[index as u32])?;
+            let entry = *response.first().ok_or(Error::doe_error(&format!(
+                "DOE mailbox at {:#x} returned an empty discovery response",
+                self.offset
+            )))?;
+
+            let protocol = DataObjectProtocol::new(entry as u16, (entry >> 16) as u8);
+            if protocols.contains(&protocol) {
+                break;
+            }
+            protocols.push(protocol);
+
+            index = (entry >> 24) as u8;
+            if index == 0 {
+                break;
+            }
+        }
+
+        Ok(protocols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    const OFFSET: u16 = 0x100;
+
+    // A mailbox that answers discovery requests as soon as Go is written.
+    struct MockMailbox {
+        config: RefCell<Vec<u8>>,
+        request: RefCell<Vec<u32>>,
+        response: RefCell<VecDeque<u32>>,
+        protocols: Vec<DataObjectProtocol>,
+    }
+
+    impl MockMailbox {
+        fn set_register(&self, register: u16, value: u32) {
+            let offset = (OFFSET + register) as usize;
+            self.config.borrow_mut()[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        fn respond(&self) {
+            let request: Vec<_> = self.request.borrow_mut().drain(..).collect();
+            let index = (request[2] & 0xff) as usize;
+            let protocol = self.protocols[index];
+            let next = match index + 1 < self.protocols.len() {
+                true => index + 1,
+                false => 0,
+            };
+
+            self.response.borrow_mut().extend([
+                request[0],
+                3,
+                protocol.header() | (next as u32) << 24,
+            ]);
+            self.pop();
+        }
+
+        fn pop(&self) {
+            match self.response.borrow().front() {
+                Some(dword) => {
+                    self.set_register(Doe::READ_MAILBOX, *dword);
+                    self.set_register(Doe::STATUS, Doe::STATUS_DATA_OBJECT_READY);
+                }
+                None => self.set_register(Doe::STATUS, 0),
+            }
+        }
+    }
+
+    impl Access for MockMailbox {
+        fn read(&self, offset: u64, length: usize) -> Result<Vec<u8>> {
+            Ok(self.config.borrow()[offset as usize..offset as usize + length].to_vec())
+        }
+
+        fn write(&self, offset: u64, buffer: &[u8]) -> Result<usize> {
+            let value = BinaryParser::le32(buffer, 0..4)?;
+
+            match (offset - OFFSET as u64) as u16 {
+                Doe::WRITE_MAILBOX => self.request.borrow_mut().push(value),
+                Doe::CONTROL if value & Doe::CONTROL_GO != 0 => self.respond(),
+                Doe::READ_MAILBOX => {
+                    self.response.borrow_mut().pop_front();
+                    self.pop();
+                }
+                _ => (),
+            }
+
+            Ok(buffer.len())
+        }
+    }
+
+    #[test]
+    fn test_discovery() {
+        let mut config = vec![0; 0x1000];
+        config[0x100..0x104].copy_from_slice(&0x0001_002eu32.to_le_bytes());
+
+        let protocols = vec![
+            DataObjectProtocol::DISCOVERY,
+            DataObjectProtocol::CMA_SPDM,
+            DataObjectProtocol::CDAT,
+        ];
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(MockMailbox {
+            config: RefCell::new(config),
+            request: RefCell::new(vec![]),
+            response: RefCell::new(VecDeque::new()),
+            protocols: protocols.clone(),
+        }));
+
+        let doe = Doe::find_all(&access).unwrap();
+        assert_eq!(doe.len(), 1);
+
+        let mut mailbox = Mailbox::new(access, OFFSET);
+        mailbox.set_timeout(Duration::from_millis(10));
+
+        let discovered = mailbox.discover().unwrap();
+        assert_eq!(discovered, protocols);
+        assert_eq!(
+            discovered
+                .iter()
+                .map(|protocol| protocol.to_string())
+                .collect::<Vec<_>>(),
+            ["Discovery", "CMA/SPDM", "CDAT"]
+        );
+    }
+}
@@ -14,6 +14,9 @@ pub enum ErrorKind {
     FormatError,
     SliceParseError,
     UnknownCapabilityId,
+    MarginingError,
+    DoeError,
+    VpdError,
 }
 
 #[derive(Debug, PartialEq)]
@@ -53,6 +56,27 @@ impl Error {
         }
     }
 
+    pub fn margining_error(message: &str) -> Error {
+        Error {
+            error_kind: ErrorKind::MarginingError,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn doe_error(message: &str) -> Error {
+        Error {
+            error_kind: ErrorKind::DoeError,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn vpd_error(message: &str) -> Error {
+        Error {
+            error_kind: ErrorKind::VpdError,
+            message: message.to_string(),
+        }
+    }
+
     pub fn unknown_capability(id: u8) -> Error {
         let message = format!("Unknown capability id:{}", id);
         Error {
@@ -82,7 +106,7 @@ impl From<std::io::Error> for Error {
 
 impl From<Error> for std::io::Error {
     fn from(value: Error) -> Self {
-        std::io::Error::new(std::io::ErrorKind::Other, value.message)
+        std::io::Error::other(value.message)
     }
 }
 
@@ -1,15 +1,59 @@
 use crate::access::Access;
+use crate::bar::BAR;
 use crate::bdf::BusDeviceFunction;
+use crate::caps::af::AdvancedFeaturesCapability;
+use crate::caps::dsn::DeviceSerialNumberCapability;
+use crate::caps::ea::EnhancedAllocationCapability;
 use crate::caps::header::CommonHeader;
 use crate::caps::header::Header;
+use crate::caps::pci_express::PciExpressCapability;
+use crate::caps::power_management::PowerManagementCapability;
+use crate::caps::registry::CapabilityRegistry;
+use crate::caps::resizable_bar::ResizableBarCapability;
+use crate::caps::sriov::SriovCapability;
+use crate::caps::ssvid::BridgeSubsystemCapability;
+use crate::caps::vpd::VitalProductDataCapability;
 use crate::caps::Capability;
 use crate::caps::CapabilityFactory;
 use crate::error::Result;
 use crate::kernel::Kernel;
 use crate::vdc::VendorDeviceClass;
+use crate::vpd::Vpd;
 use std::fmt::Display;
 use std::rc::Rc;
 
+/// A way of resetting a single function, named as in the kernel `reset_method` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetMethod {
+    /// PCIe Function Level Reset, advertised in DevCap.
+    Flr,
+    /// Function Level Reset through the Advanced Features capability.
+    AfFlr,
+    /// D3hot->D0 transition of a function that does not set No_Soft_Reset.
+    Pm,
+    /// Secondary bus reset of the parent bridge, when the function is alone on its bus.
+    Bus,
+}
+
+impl Display for ResetMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResetMethod::Flr => write!(f, "flr"),
+            ResetMethod::AfFlr => write!(f, "af_flr"),
+            ResetMethod::Pm => write!(f, "pm"),
+            ResetMethod::Bus => write!(f, "bus"),
+        }
+    }
+}
+
+/// Reset methods found in config space alongside the ones the kernel reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResetMethods {
+    pub supported: Vec<ResetMethod>,
+    /// Contents of the sysfs `reset_method` attribute, None if it is not available.
+    pub kernel: Option<Vec<String>>,
+}
+
 pub struct Function {
     bdf: BusDeviceFunction,
     header: Header,
@@ -24,12 +68,40 @@ impl Function {
         accessor: Rc<Box<dyn Access>>,
         kernel: Kernel,
     ) -> Result<Self> {
+        Self::with_registry(
+            bdf,
+            accessor,
+            kernel,
+            Rc::new(CapabilityRegistry::default()),
+        )
+    }
+
+    pub fn with_registry(
+        bdf: BusDeviceFunction,
+        accessor: Rc<Box<dyn Access>>,
+        kernel: Kernel,
+        registry: Rc<CapabilityRegistry>,
+    ) -> Result<Self> {
+        let mut header = Header::new(&accessor.read(0, 0x40)?)?;
+        match &mut header {
+            Header::Type0(h) => {
+                if let Ok(Some(ea)) = EnhancedAllocationCapability::find(&accessor) {
+                    h.set_enhanced_allocation(ea.resources());
+                }
+            }
+            Header::Type1(h) => {
+                if let Ok(Some(ssvid)) = BridgeSubsystemCapability::find(&accessor) {
+                    h.set_subsystem(ssvid.subsystem_vendor_id(), ssvid.subsystem_id());
+                }
+            }
+        }
+
         let function = Function {
             bdf,
-            header: Header::new(&accessor.read(0, 0x40)?)?,
+            header,
             kernel,
             access: Rc::clone(&accessor),
-            capabilities: CapabilityFactory::new(accessor).scan(),
+            capabilities: CapabilityFactory::with_registry(accessor, registry).scan(),
         };
 
         Ok(function)
@@ -62,17 +134,104 @@ impl Function {
     pub fn subsystem_vendor_id(&self) -> Result<Option<u16>> {
         match &self.header {
             Header::Type0(h) => Ok(Some(h.subsystem_vendor_id()?)),
-            _ => Ok(None),
+            Header::Type1(h) => Ok(h.subsystem_vendor_id()),
         }
     }
 
     pub fn subsystem_id(&self) -> Result<Option<u16>> {
         match &self.header {
             Header::Type0(h) => Ok(Some(h.subsystem_id()?)),
-            _ => Ok(None),
+            Header::Type1(h) => Ok(h.subsystem_id()),
         }
     }
 
+    pub fn bdf(&self) -> &BusDeviceFunction {
+        &self.bdf
+    }
+
+    pub fn access(&self) -> Rc<Box<dyn Access>> {
+        Rc::clone(&self.access)
+    }
+
+    pub fn is_multifunction(&self) -> Result<bool> {
+        self.header.is_multifunction()
+    }
+
+    pub fn virtual_functions(&self) -> Result<Vec<BusDeviceFunction>> {
+        match CapabilityFactory::lookup(Rc::clone(&self.access)).find_extended(0x0010)? {
+            Some(offset) => {
+                let vfs = SriovCapability::new(Rc::clone(&self.access), offset)?
+                    .virtual_functions(&self.bdf)?;
+                match self.kernel.ari_forwarding(&self.bdf) {
+                    true => Ok(vfs.iter().map(BusDeviceFunction::to_ari).collect()),
+                    false => Ok(vfs),
+                }
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    pub fn serial_number(&self) -> Result<Option<u64>> {
+        Ok(DeviceSerialNumberCapability::find(&self.access)?.map(|cap| cap.serial_number()))
+    }
+
+    pub fn vital_product_data(&self) -> Result<Option<Vpd>> {
+        match VitalProductDataCapability::find(&self.access)? {
+            Some(cap) => Ok(Some(cap.vpd()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn reset_methods(&self) -> Result<ResetMethods> {
+        let mut supported = vec![];
+
+        if let Some(cap) = PciExpressCapability::find(&self.access)? {
+            if cap.function_level_reset_capable()? {
+                supported.push(ResetMethod::Flr);
+            }
+        }
+
+        if let Some(cap) = AdvancedFeaturesCapability::find(&self.access)? {
+            if cap.transactions_pending_capable() && cap.function_level_reset_capable() {
+                supported.push(ResetMethod::AfFlr);
+            }
+        }
+
+        if let Some(cap) = PowerManagementCapability::find(&self.access)? {
+            if !cap.no_soft_reset()? {
+                supported.push(ResetMethod::Pm);
+            }
+        }
+
+        // Bridges take their subordinate buses down with a secondary bus reset
+        if let Header::Type0(_) = self.header {
+            if self.kernel.bus_reset_capable(&self.bdf) {
+                supported.push(ResetMethod::Bus);
+            }
+        }
+
+        Ok(ResetMethods {
+            supported,
+            kernel: self.kernel.reset_methods(&self.bdf)?,
+        })
+    }
+
+    /// Returns the BARs of the header followed by the resources assigned through Enhanced
+    /// Allocation.
+    pub fn resources(&self) -> Result<Vec<BAR>> {
+        let mut resources = self.header.bars()?;
+        if let Header::Type0(h) = &self.header {
+            resources.extend(h.enhanced_allocation().iter().cloned());
+        }
+        Ok(resources)
+    }
+
+    /// Returns the sizes in bytes BAR `index` can be resized to, or None if it is not resizable.
+    pub fn resizable_bar_sizes(&self, index: u8) -> Result<Option<Vec<u64>>> {
+        Ok(ResizableBarCapability::find(&self.access)?
+            .and_then(|cap| cap.entry(index).map(|entry| entry.supported_sizes())))
+    }
+
     pub fn config_with_verbosity(&self, verbosity: u8) -> Result<Vec<u8>> {
         let mut config = vec![];
 
@@ -99,9 +258,14 @@ impl Function {
                 Err(_) => text += "\tCapabilities: <access denied>\n",
                 Ok(capabilities) => {
                     for cap in capabilities {
+                        let version = match cap.version()? {
+                            Some(version) => format!(" v{}", version),
+                            None => String::new(),
+                        };
                         text += &format!(
-                            "\tCapabilities: [{:x}] {}\n",
+                            "\tCapabilities: [{:x}{}] {}\n",
                             cap.offset()?,
+                            version,
                             cap.cap_string(verbosity)?
                         );
                     }
@@ -1,7 +1,7 @@
 use crate::access::sysfs::Sysfs;
 use crate::bdf::BusDeviceFunction;
 use crate::error::Result;
-use std::fs::read_link;
+use std::fs::{self, read_link};
 
 #[derive(Debug)]
 pub struct Kernel;
@@ -28,7 +28,7 @@ impl Kernel {
 
         Ok(format!(
             "\tKernel driver in use: {}",
-            driver_path.split('/').last().unwrap_or_default()
+            driver_path.split('/').next_back().unwrap_or_default()
         ))
     }
 
@@ -45,7 +45,32 @@ impl Kernel {
 
         Ok(format!(
             "\tKernel modules: {}",
-            module_path.split('/').last().unwrap_or_default()
+            module_path.split('/').next_back().unwrap_or_default()
         ))
     }
+
+    /// Whether functions below the same port as `bdf` are addressed with ARI function numbers.
+    pub fn ari_forwarding(&self, bdf: &BusDeviceFunction) -> bool {
+        Sysfs::ari_forwarding(bdf)
+    }
+
+    /// Whether `bdf` sits alone below a bridge, so that a secondary bus reset resets nothing
+    /// else.
+    pub fn bus_reset_capable(&self, bdf: &BusDeviceFunction) -> bool {
+        let bridges = Sysfs::upstream_bridges(bdf).unwrap_or_default();
+        let siblings = Sysfs::sibling_functions(bdf).unwrap_or_default();
+        !bridges.is_empty() && siblings.is_empty()
+    }
+
+    /// Returns the reset methods the kernel will try for `bdf`, in order, or None if the kernel
+    /// does not expose the `reset_method` attribute.
+    pub fn reset_methods(&self, bdf: &BusDeviceFunction) -> Result<Option<Vec<String>>> {
+        match fs::read_to_string(Sysfs::get_function_sub_path(bdf, "reset_method")) {
+            Ok(methods) => Ok(Some(
+                methods.split_whitespace().map(str::to_string).collect(),
+            )),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
 }
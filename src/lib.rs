@@ -2,8 +2,11 @@ pub mod access;
 pub mod bar;
 pub mod bdf;
 pub mod caps;
+pub mod doe;
 pub mod error;
 pub mod function;
 pub mod kernel;
+pub mod margining;
 pub mod parser;
 pub mod vdc;
+pub mod vpd;
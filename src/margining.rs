@@ -0,0 +1,436 @@
+use crate::access::Access;
+use crate::caps::binary_parser::BinaryParser;
+use crate::caps::lane_margining::{LaneMarginingCapability, MarginingLaneRegister};
+use crate::error::{Error, Result};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const NO_COMMAND: (u8, u8) = (0x7, 0x9c);
+
+const REPORT: u8 = 0x1;
+const SET: u8 = 0x2;
+const STEP_TIMING: u8 = 0x3;
+const STEP_VOLTAGE: u8 = 0x4;
+
+const REPORT_CAPABILITIES: u8 = 0x88;
+const REPORT_VOLTAGE_STEPS: u8 = 0x89;
+const REPORT_TIMING_STEPS: u8 = 0x8a;
+const REPORT_MAX_TIMING_OFFSET: u8 = 0x8b;
+const REPORT_MAX_VOLTAGE_OFFSET: u8 = 0x8c;
+const REPORT_SAMPLING_RATE_VOLTAGE: u8 = 0x8d;
+const REPORT_SAMPLING_RATE_TIMING: u8 = 0x8e;
+const REPORT_MAX_LANES: u8 = 0x90;
+
+const SET_ERROR_COUNT_LIMIT: u8 = 0xc0;
+const GO_TO_NORMAL_SETTINGS: u8 = 0x0f;
+const CLEAR_ERROR_LOG: u8 = 0x55;
+
+/// Margining parameters reported by a receiver.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MarginingCapabilities {
+    pub voltage_supported: bool,
+    pub independent_up_down_voltage: bool,
+    pub independent_left_right_timing: bool,
+    pub sample_reporting_method: bool,
+    pub independent_error_sampler: bool,
+    pub voltage_steps: u8,
+    pub timing_steps: u8,
+    /// Maximum timing offset in percent of a UI.
+    pub max_timing_offset: u8,
+    /// Maximum voltage offset in units of 10mV.
+    pub max_voltage_offset: u8,
+    pub sampling_rate_voltage: u8,
+    pub sampling_rate_timing: u8,
+    pub max_lanes: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarginDirection {
+    Timing { left: bool },
+    Voltage { down: bool },
+}
+
+/// Execution status reported in the payload of a step margin response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarginStatus {
+    TooManyErrors(u8),
+    SetUpInProgress,
+    MarginingInProgress(u8),
+    Nak,
+}
+
+impl MarginStatus {
+    pub fn new(payload: u8) -> MarginStatus {
+        let errors = payload & 0x3f;
+        match payload >> 6 {
+            0 => MarginStatus::TooManyErrors(errors),
+            1 => MarginStatus::SetUpInProgress,
+            2 => MarginStatus::MarginingInProgress(errors),
+            _ => MarginStatus::Nak,
+        }
+    }
+}
+
+/// Outcome of margining one direction of one lane.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarginResult {
+    pub direction: MarginDirection,
+    /// Last step at which the error count stayed within the limit.
+    pub steps: u8,
+    /// Error count observed at the last passing step.
+    pub errors: u8,
+    /// Offset of the last passing step, in percent of a UI for timing and mV for voltage.
+    pub offset: f64,
+}
+
+/// Runs the lane margining command protocol against one receiver of a port's lane, through the
+/// Lane Margining at the Receiver capability at `offset`.
+pub struct LaneMargining {
+    access: Rc<Box<dyn Access>>,
+    offset: u16,
+    lane: u8,
+    receiver: u8,
+
+    timeout: Duration,
+    dwell: Duration,
+    error_limit: u8,
+}
+
+impl LaneMargining {
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u16, lane: u8, receiver: u8) -> LaneMargining {
+        LaneMargining {
+            access,
+            offset,
+            lane,
+            receiver,
+            timeout: Duration::from_millis(10),
+            dwell: Duration::from_millis(100),
+            error_limit: 4,
+        }
+    }
+
+    /// Sets how long a command may take to be acknowledged and how long each margin step is held
+    /// before its error count is read.
+    pub fn set_timing(&mut self, timeout: Duration, dwell: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self.dwell = dwell;
+        self
+    }
+
+    pub fn set_error_limit(&mut self, error_limit: u8) -> &mut Self {
+        self.error_limit = error_limit & 0x3f;
+        self
+    }
+
+    pub fn port_ready(&self) -> Result<bool> {
+        Ok(LaneMarginingCapability::new(Rc::clone(&self.access), self.offset)?.ready())
+    }
+
+    fn write_control(&self, receiver: u8, margin_type: u8, payload: u8) -> Result<()> {
+        let control = MarginingLaneRegister::new(receiver, margin_type, payload);
+        self.access.write(
+            LaneMarginingCapability::lane_control_offset(self.offset, self.lane).into(),
+            &control.register().to_le_bytes(),
+        )?;
+        Ok(())
+    }
+
+    fn read_status(&self) -> Result<MarginingLaneRegister> {
+        let raw = self.access.read(
+            LaneMarginingCapability::lane_status_offset(self.offset, self.lane).into(),
+            2,
+        )?;
+        Ok(MarginingLaneRegister::from_register(BinaryParser::le16(
+            &raw,
+            0..2,
+        )?))
+    }
+
+    fn wait_for(&self, receiver: u8, margin_type: u8, payload: Option<u8>) -> Result<u8> {
+        let start = Instant::now();
+
+        loop {
+            let status = self.read_status()?;
+            if status.receiver == receiver
+                && status.margin_type == margin_type
+                && (payload.is_none() || payload == Some(status.payload))
+            {
+                return Ok(status.payload);
+            }
+
+            if start.elapsed() > self.timeout {
+                return Err(Error::margining_error(&format!(
+                    "Lane {} receiver {} did not respond to margin type {}",
+                    self.lane, self.receiver, margin_type
+                )));
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Issues a margin command and returns the payload of the receiver's response. A No Command
+    /// is issued first so that a stale status from a previous command is never mistaken for the
+    /// response. No Command is always addressed to receiver 0 as the specification requires.
+    pub fn command(&self, margin_type: u8, payload: u8) -> Result<u8> {
+        self.write_control(0, NO_COMMAND.0, NO_COMMAND.1)?;
+        self.wait_for(0, NO_COMMAND.0, Some(NO_COMMAND.1))?;
+
+        self.write_control(self.receiver, margin_type, payload)?;
+        match margin_type {
+            SET => self.wait_for(self.receiver, margin_type, Some(payload)),
+            _ => self.wait_for(self.receiver, margin_type, None),
+        }
+    }
+
+    fn report(&self, payload: u8) -> Result<u8> {
+        self.command(REPORT, payload)
+    }
+
+    pub fn capabilities(&self) -> Result<MarginingCapabilities> {
+        let flags = self.report(REPORT_CAPABILITIES)?;
+
+        Ok(MarginingCapabilities {
+            voltage_supported: flags & (1 << 0) != 0,
+            independent_up_down_voltage: flags & (1 << 1) != 0,
+            independent_left_right_timing: flags & (1 << 2) != 0,
+            sample_reporting_method: flags & (1 << 3) != 0,
+            independent_error_sampler: flags & (1 << 4) != 0,
+            voltage_steps: self.report(REPORT_VOLTAGE_STEPS)? & 0x7f,
+            timing_steps: self.report(REPORT_TIMING_STEPS)? & 0x3f,
+            max_timing_offset: self.report(REPORT_MAX_TIMING_OFFSET)? & 0x7f,
+            max_voltage_offset: self.report(REPORT_MAX_VOLTAGE_OFFSET)? & 0x7f,
+            sampling_rate_voltage: self.report(REPORT_SAMPLING_RATE_VOLTAGE)? & 0x3f,
+            sampling_rate_timing: self.report(REPORT_SAMPLING_RATE_TIMING)? & 0x3f,
+            max_lanes: self.report(REPORT_MAX_LANES)? & 0x1f,
+        })
+    }
+
+    pub fn go_to_normal_settings(&self) -> Result<()> {
+        self.command(SET, GO_TO_NORMAL_SETTINGS)?;
+        Ok(())
+    }
+
+    pub fn clear_error_log(&self) -> Result<()> {
+        self.command(SET, CLEAR_ERROR_LOG)?;
+        Ok(())
+    }
+
+    fn step(&self, direction: MarginDirection, steps: u8) -> Result<MarginStatus> {
+        let (margin_type, payload) = match direction {
+            MarginDirection::Timing { left } => (STEP_TIMING, (left as u8) << 6 | steps & 0x3f),
+            MarginDirection::Voltage { down } => (STEP_VOLTAGE, (down as u8) << 7 | steps & 0x7f),
+        };
+
+        let start = Instant::now();
+        let mut status = MarginStatus::new(self.command(margin_type, payload)?);
+        while status == MarginStatus::SetUpInProgress {
+            if start.elapsed() > self.timeout {
+                return Err(Error::margining_error(&format!(
+                    "Lane {} receiver {} did not finish margin set-up",
+                    self.lane, self.receiver
+                )));
+            }
+            thread::sleep(Duration::from_millis(1));
+            status = MarginStatus::new(self.wait_for(self.receiver, margin_type, None)?);
+        }
+
+        if let MarginStatus::MarginingInProgress(_) = status {
+            thread::sleep(self.dwell);
+            status = MarginStatus::new(self.wait_for(self.receiver, margin_type, None)?);
+        }
+
+        Ok(status)
+    }
+
+    /// Steps the receiver one step further at a time and returns the last step whose error count
+    /// stayed within the limit, leaving the receiver margined.
+    fn step_until_errors(&self, direction: MarginDirection, max_steps: u8) -> Result<MarginResult> {
+        let mut result = MarginResult {
+            direction,
+            steps: 0,
+            errors: 0,
+            offset: 0.0,
+        };
+
+        for step in 1..=max_steps {
+            match self.step(direction, step)? {
+                MarginStatus::MarginingInProgress(errors) => {
+                    result.steps = step;
+                    result.errors = errors;
+                }
+                MarginStatus::TooManyErrors(_) => break,
+                MarginStatus::Nak | MarginStatus::SetUpInProgress => {
+                    return Err(Error::margining_error(&format!(
+                        "Lane {} receiver {} rejected margin step {}",
+                        self.lane, self.receiver, step
+                    )));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Steps the receiver away from its normal sampling point in `direction` until the error
+    /// count exceeds the limit or the last step is reached, then returns it to normal settings.
+    pub fn margin(
+        &self,
+        capabilities: &MarginingCapabilities,
+        direction: MarginDirection,
+    ) -> Result<MarginResult> {
+        let (max_steps, max_offset) = match direction {
+            MarginDirection::Timing { .. } => (
+                capabilities.timing_steps,
+                capabilities.max_timing_offset as f64,
+            ),
+            MarginDirection::Voltage { .. } => {
+                if !capabilities.voltage_supported {
+                    return Err(Error::margining_error(&format!(
+                        "Receiver {} does not support voltage margining",
+                        self.receiver
+                    )));
+                }
+                (
+                    capabilities.voltage_steps,
+                    capabilities.max_voltage_offset as f64 * 10.0,
+                )
+            }
+        };
+
+        // Without independent margining the direction bit is reserved and must stay clear
+        let direction = match direction {
+            MarginDirection::Timing { left } => MarginDirection::Timing {
+                left: left && capabilities.independent_left_right_timing,
+            },
+            MarginDirection::Voltage { down } => MarginDirection::Voltage {
+                down: down && capabilities.independent_up_down_voltage,
+            },
+        };
+
+        self.clear_error_log()?;
+        self.command(SET, SET_ERROR_COUNT_LIMIT | self.error_limit)?;
+
+        // The receiver is returned to normal settings however stepping ends
+        let stepped = self.step_until_errors(direction, max_steps);
+        let restored = self.go_to_normal_settings();
+        let mut result = stepped?;
+        restored?;
+        self.clear_error_log()?;
+
+        if max_steps > 0 {
+            result.offset = result.steps as f64 * max_offset / max_steps as f64;
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    // A receiver whose eye is open for `eye` timing steps and which answers margin commands as
+    // soon as they are written.
+    struct SimulatedReceiver {
+        config: RefCell<Vec<u8>>,
+        capabilities: u8,
+        eye: u8,
+    }
+
+    impl SimulatedReceiver {
+        fn respond(&self, control: MarginingLaneRegister) -> MarginingLaneRegister {
+            let payload = match (control.margin_type, control.payload) {
+                (REPORT, REPORT_CAPABILITIES) => self.capabilities,
+                (REPORT, REPORT_TIMING_STEPS) => 16,
+                (REPORT, REPORT_MAX_TIMING_OFFSET) => 40,
+                (REPORT, _) => 0,
+                (STEP_TIMING, payload)
+                    if payload & (1 << 6) != 0 && self.capabilities & (1 << 2) == 0 =>
+                {
+                    0xc0
+                }
+                (STEP_TIMING, payload) if payload & 0x3f <= self.eye => {
+                    0x80 | ((payload & 0x3f) / 4)
+                }
+                (STEP_TIMING, _) => 0x05,
+                (_, payload) => payload,
+            };
+
+            MarginingLaneRegister { payload, ..control }
+        }
+    }
+
+    impl Access for SimulatedReceiver {
+        fn read(&self, offset: u64, length: usize) -> Result<Vec<u8>> {
+            Ok(self.config.borrow()[offset as usize..offset as usize + length].to_vec())
+        }
+
+        fn write(&self, offset: u64, buffer: &[u8]) -> Result<usize> {
+            let mut config = self.config.borrow_mut();
+            config[offset as usize..offset as usize + buffer.len()].copy_from_slice(buffer);
+
+            let control =
+                MarginingLaneRegister::from_register(BinaryParser::le16(&config, 0x208..0x20a)?);
+            // No Command is only valid when addressed to receiver 0
+            if control.margin_type == NO_COMMAND.0 && control.receiver != 0 {
+                return Ok(buffer.len());
+            }
+            let status = self.respond(control);
+            config[0x20a..0x20c].copy_from_slice(&status.register().to_le_bytes());
+
+            Ok(buffer.len())
+        }
+    }
+
+    #[test]
+    fn test_timing_margin() {
+        let mut config = vec![0; 0x1000];
+        config[0x200..0x204].copy_from_slice(&0x0001_0027u32.to_le_bytes());
+        config[0x206] = 0x01;
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(SimulatedReceiver {
+            config: RefCell::new(config.clone()),
+            capabilities: 0b00100,
+            eye: 10,
+        }));
+
+        let mut margining = LaneMargining::new(access, 0x200, 0, 1);
+        margining.set_timing(Duration::from_millis(10), Duration::ZERO);
+        assert!(margining.port_ready().unwrap());
+
+        let capabilities = margining.capabilities().unwrap();
+        assert!(capabilities.independent_left_right_timing);
+        assert!(!capabilities.voltage_supported);
+        assert_eq!(capabilities.timing_steps, 16);
+
+        let result = margining
+            .margin(&capabilities, MarginDirection::Timing { left: true })
+            .unwrap();
+        assert_eq!(result.steps, 10);
+        assert_eq!(result.errors, 2);
+        assert_eq!(result.offset, 25.0);
+
+        assert!(margining
+            .margin(&capabilities, MarginDirection::Voltage { down: false })
+            .is_err());
+
+        let access: Rc<Box<dyn Access>> = Rc::new(Box::new(SimulatedReceiver {
+            config: RefCell::new(config),
+            capabilities: 0b00000,
+            eye: 10,
+        }));
+
+        let mut margining = LaneMargining::new(access, 0x200, 0, 1);
+        margining.set_timing(Duration::from_millis(10), Duration::ZERO);
+
+        let capabilities = margining.capabilities().unwrap();
+        assert!(!capabilities.independent_left_right_timing);
+
+        let result = margining
+            .margin(&capabilities, MarginDirection::Timing { left: true })
+            .unwrap();
+        assert_eq!(result.steps, 10);
+    }
+}
@@ -0,0 +1,316 @@
+use crate::access::{Access, VpdSource};
+use crate::caps::binary_parser::BinaryParser;
+use crate::error::{Error, Result};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const IDENTIFIER_STRING: u8 = 0x02;
+const READ_ONLY: u8 = 0x10;
+const READ_WRITE: u8 = 0x11;
+const END: u8 = 0x0f;
+
+/// One keyword of a VPD-R or VPD-W resource.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VpdKeyword {
+    pub keyword: String,
+    pub data: Vec<u8>,
+}
+
+impl VpdKeyword {
+    pub fn name(&self) -> &'static str {
+        match self.keyword.as_str() {
+            "CP" => "Extended capability",
+            "EC" => "Engineering changes",
+            "FG" => "Fabric geography",
+            "LC" => "Location",
+            "MN" => "Manufacture ID",
+            "PG" => "PCI geography",
+            "PN" => "Part number",
+            "RV" => "Reserved",
+            "RW" => "Read-write area",
+            "SN" => "Serial number",
+            "YA" => "Asset tag",
+            keyword if keyword.starts_with('V') => "Vendor specific",
+            keyword if keyword.starts_with('Y') => "System specific",
+            _ => "Unknown",
+        }
+    }
+
+    /// The keyword data as text, with trailing NULs dropped and other unprintable bytes escaped.
+    pub fn text(&self) -> String {
+        let data = match self.data.iter().rposition(|byte| *byte != 0) {
+            Some(end) => &self.data[..=end],
+            None => &[],
+        };
+
+        data.iter()
+            .map(|byte| match byte {
+                b'\\' => "\\\\".to_string(),
+                0x20..=0x7e => (*byte as char).to_string(),
+                _ => format!("\\x{:0>2x}", byte),
+            })
+            .collect()
+    }
+}
+
+/// Parsed Vital Product Data resources.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Vpd {
+    pub identifier: Option<String>,
+    pub read_only: Vec<VpdKeyword>,
+    pub read_write: Vec<VpdKeyword>,
+    /// Whether the bytes up to the RV keyword sum to zero, None without an RV keyword.
+    pub checksum_valid: Option<bool>,
+}
+
+impl Vpd {
+    pub fn parse(raw: &[u8]) -> Result<Vpd> {
+        let mut vpd = Vpd::default();
+        let mut offset = 0;
+
+        loop {
+            let (tag, start, length) = resource(raw, offset)?;
+            let end = start + length;
+            let data = raw.get(start..end).ok_or(Error::vpd_error(&format!(
+                "Resource at {:#x} is truncated",
+                offset
+            )))?;
+
+            match tag {
+                END => break,
+                IDENTIFIER_STRING => {
+                    vpd.identifier = Some(String::from_utf8_lossy(data).trim().to_string())
+                }
+                READ_ONLY => {
+                    vpd.read_only = Self::keywords(data)?;
+                    if let Some(rv) = Self::keyword_offset(data, "RV") {
+                        // An RV keyword without data has no checksum byte to check
+                        vpd.checksum_valid = Some(match raw.get(..=start + rv) {
+                            Some(checked) if rv < data.len() => {
+                                checked
+                                    .iter()
+                                    .fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+                                    == 0
+                            }
+                            _ => false,
+                        });
+                    }
+                }
+                READ_WRITE => vpd.read_write = Self::keywords(data)?,
+                _ => {
+                    return Err(Error::vpd_error(&format!(
+                        "Unknown resource type {:0>2x} at {:#x}",
+                        tag, offset
+                    )))
+                }
+            }
+
+            offset = end;
+        }
+
+        Ok(vpd)
+    }
+
+    fn keywords(data: &[u8]) -> Result<Vec<VpdKeyword>> {
+        let mut keywords = vec![];
+        let mut offset = 0;
+
+        while offset + 3 <= data.len() {
+            let length = data[offset + 2] as usize;
+            let value = data
+                .get(offset + 3..offset + 3 + length)
+                .ok_or(Error::vpd_error(&format!(
+                    "Keyword at {:#x} is truncated",
+                    offset
+                )))?;
+
+            keywords.push(VpdKeyword {
+                keyword: String::from_utf8_lossy(&data[offset..offset + 2]).to_string(),
+                data: value.to_vec(),
+            });
+            offset += 3 + length;
+        }
+
+        Ok(keywords)
+    }
+
+    // Offset of the first data byte of `keyword` within a VPD-R resource.
+    fn keyword_offset(data: &[u8], keyword: &str) -> Option<usize> {
+        let mut offset = 0;
+
+        while offset + 3 <= data.len() {
+            if &data[offset..offset + 2] == keyword.as_bytes() {
+                return Some(offset + 3);
+            }
+            offset += 3 + data[offset + 2] as usize;
+        }
+
+        None
+    }
+
+    pub fn keyword(&self, keyword: &str) -> Option<&VpdKeyword> {
+        self.read_only
+            .iter()
+            .chain(self.read_write.iter())
+            .find(|entry| entry.keyword == keyword)
+    }
+
+    pub fn part_number(&self) -> Option<String> {
+        self.keyword("PN").map(|keyword| keyword.text())
+    }
+
+    pub fn serial_number(&self) -> Option<String> {
+        self.keyword("SN").map(|keyword| keyword.text())
+    }
+}
+
+/// Returns the tag, data offset and data length of the resource at `offset`.
+fn resource(raw: &[u8], offset: usize) -> Result<(u8, usize, usize)> {
+    let tag = *raw
+        .get(offset)
+        .ok_or(Error::vpd_error("VPD ends without an end tag"))?;
+
+    match tag & 0x80 != 0 {
+        true => Ok((
+            tag & 0x7f,
+            offset + 3,
+            BinaryParser::le16(raw, offset + 1..offset + 3)? as usize,
+        )),
+        false => Ok(((tag >> 3) & 0xf, offset + 1, (tag & 0x7) as usize)),
+    }
+}
+
+/// Reads VPD through the VPD capability at `offset`, unless the backend exposes it directly.
+pub struct VpdReader {
+    access: Rc<Box<dyn Access>>,
+    offset: u8,
+    timeout: Duration,
+}
+
+impl VpdReader {
+    const SIZE: usize = 0x8000;
+
+    pub fn new(access: Rc<Box<dyn Access>>, offset: u8) -> VpdReader {
+        VpdReader {
+            access,
+            offset,
+            timeout: Duration::from_millis(125),
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn read_dword(&self, address: u16) -> Result<Vec<u8>> {
+        self.access
+            .write(self.offset as u64 + 2, &(address & 0x7ffc).to_le_bytes())?;
+
+        let start = Instant::now();
+        loop {
+            let register = BinaryParser::le16(&self.access.read(self.offset as u64 + 2, 2)?, 0..2)?;
+            if register & 0x8000 != 0 && register & 0x7ffc == address & 0x7ffc {
+                return self.access.read(self.offset as u64 + 4, 4);
+            }
+
+            if start.elapsed() > self.timeout {
+                return Err(Error::vpd_error(&format!(
+                    "VPD read of address {:#x} timed out",
+                    address
+                )));
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Reads `length` bytes of VPD starting at `address` with the address/flag handshake.
+    pub fn read(&self, address: usize, length: usize) -> Result<Vec<u8>> {
+        let mut buffer = vec![];
+
+        let mut dword = address & !0x3;
+        while dword < address + length {
+            buffer.extend(self.read_dword(dword as u16)?);
+            dword += 4;
+        }
+
+        let range = address & 0x3..(address & 0x3) + length;
+        Ok(buffer
+            .get(range.clone())
+            .ok_or(Error::slice_parse_error(&buffer, &range))?
+            .to_vec())
+    }
+
+    /// Reads the raw VPD resources up to and including the end tag.
+    pub fn read_all(&self) -> Result<Vec<u8>> {
+        match self.access.vpd()? {
+            VpdSource::Handshake => (),
+            VpdSource::Unavailable => return Err(Error::vpd_error("VPD is not available")),
+            VpdSource::Data(vpd) => return Ok(vpd),
+        }
+
+        let mut raw = vec![];
+        loop {
+            let offset = raw.len();
+            if offset >= Self::SIZE {
+                return Err(Error::vpd_error("VPD ends without an end tag"));
+            }
+
+            raw.extend(self.read(offset, 1)?);
+            if raw[offset] & 0x80 != 0 {
+                raw.extend(self.read(offset + 1, 2)?);
+            }
+
+            let (tag, start, length) = resource(&raw, offset)?;
+            raw.extend(self.read(start, length)?);
+
+            if tag == END {
+                return Ok(raw);
+            }
+        }
+    }
+
+    pub fn vpd(&self) -> Result<Vpd> {
+        Vpd::parse(&self.read_all()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut raw = vec![0x82, 0x0b, 0x00];
+        raw.extend(b"Example NIC");
+        raw.extend([0x90, 0x14, 0x00]);
+        raw.extend(b"PN\x06ABC123");
+        raw.extend(b"SN\x04S001");
+        raw.extend(b"RV\x01\x00");
+        raw.extend([0x91, 0x05, 0x00]);
+        raw.extend(b"RW\x02\x00\x00");
+        raw.push(0x78);
+
+        let rv = raw.iter().position(|byte| *byte == b'R').unwrap() + 3;
+        let sum = raw[..rv]
+            .iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        raw[rv] = sum.wrapping_neg();
+
+        let vpd = Vpd::parse(&raw).unwrap();
+        assert_eq!(vpd.identifier.as_deref(), Some("Example NIC"));
+        assert_eq!(vpd.part_number().as_deref(), Some("ABC123"));
+        assert_eq!(vpd.serial_number().as_deref(), Some("S001"));
+        assert_eq!(vpd.checksum_valid, Some(true));
+        assert_eq!(vpd.read_write[0].name(), "Read-write area");
+
+        raw[rv] = raw[rv].wrapping_add(1);
+        assert_eq!(Vpd::parse(&raw).unwrap().checksum_valid, Some(false));
+
+        let malformed = [0x90, 0x03, 0x00, b'R', b'V', 0x00];
+        assert!(Vpd::parse(&malformed).is_err());
+        let malformed = [0x90, 0x03, 0x00, b'R', b'V', 0x00, 0x78];
+        assert_eq!(Vpd::parse(&malformed).unwrap().checksum_valid, Some(false));
+    }
+}